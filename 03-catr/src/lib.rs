@@ -1,39 +1,288 @@
-use std::io::{BufRead, BufReader};
+pub mod tac;
+
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
+const BUFFER_SIZE: usize = 128 * 1024;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const BZIP2_MAGIC: &[u8] = b"BZh";
+const XZ_MAGIC: &[u8] = &[0xfd, b'7', b'z', b'X', b'Z', 0x00];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
 pub fn run(config: Config) -> Result<()> {
-    let mut line_number = 0;
-
-    for file in config.files.iter() {
-        match open(file) {
-            Ok(file) => {
-                for line in file.lines() {
-                    let line = line?;
-                    if config.number_lines {
-                        line_number += 1;
-                        println!("{:6}\t{line}", line_number);
-                    } else if config.number_nonblank_lines && !line.is_empty() {
-                        line_number += 1;
-                        println!("{:6}\t{line}", line_number);
-                    } else {
-                        println!("{line}");
-                    }
+    let mut stdout = BufWriter::with_capacity(BUFFER_SIZE, std::io::stdout().lock());
+    let files = config.files.clone();
+    let strict = config.strict;
+    let mut cat = Cat::new(config);
+    let mut failed_files = 0;
+
+    for filename in files.iter() {
+        let summary = cat.cat_file(filename, &mut stdout)?;
+
+        if let Some(error) = summary.error {
+            stdout.flush()?;
+            eprintln!("{filename}: {error}");
+            failed_files += 1;
+
+            if strict {
+                return Err(format!("stopped at the first error in {filename}").into());
+            }
+        }
+    }
+
+    stdout.flush()?;
+
+    if failed_files > 0 {
+        return Err(format!(
+            "{failed_files} of {} file(s) could not be read",
+            files.len()
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+pub struct Cat {
+    config: Config,
+    show_nonprinting: bool,
+    show_ends: bool,
+    show_tabs: bool,
+    pass_through: bool,
+    line_number: i64,
+    previous_line_blank: bool,
+    at_line_start: bool,
+    line: Vec<u8>,
+    output: Vec<u8>,
+}
+
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub bytes: u64,
+    pub lines: u64,
+    pub error: Option<io::Error>,
+}
+
+impl Cat {
+    pub fn new(config: Config) -> Self {
+        let show_nonprinting = config.show_nonprinting || config.show_all;
+        let show_ends = config.show_ends || config.show_all;
+        let show_tabs = config.show_tabs || config.show_all;
+
+        let pass_through = !(config.number_lines
+            || config.number_nonblank_lines
+            || config.number_matching.is_some()
+            || config.squeeze_blank
+            || show_nonprinting
+            || show_ends
+            || show_tabs);
+
+        Self {
+            line_number: config.starting_line_number,
+            config,
+            show_nonprinting,
+            show_ends,
+            show_tabs,
+            pass_through,
+            previous_line_blank: false,
+            at_line_start: true,
+            line: Vec::new(),
+            output: Vec::new(),
+        }
+    }
+
+    pub fn cat_file(&mut self, filename: &str, output: impl Write) -> io::Result<Summary> {
+        match open(filename, self.config.decompress) {
+            Ok(file) => self.cat(file, output),
+            Err(error) => Ok(Summary {
+                error: Some(error),
+                ..Summary::default()
+            }),
+        }
+    }
+
+    pub fn cat(&mut self, input: impl BufRead, output: impl Write) -> io::Result<Summary> {
+        if self.config.restart_numbering {
+            self.line_number = self.config.starting_line_number;
+        }
+
+        if self.pass_through {
+            copy(input, output)
+        } else {
+            self.cat_lines(input, output)
+        }
+    }
+
+    fn cat_lines(
+        &mut self,
+        mut input: impl BufRead,
+        mut output: impl Write,
+    ) -> io::Result<Summary> {
+        let mut summary = Summary::default();
+
+        loop {
+            self.line.clear();
+            self.output.clear();
+
+            match input.read_until(b'\n', &mut self.line) {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(error) => {
+                    summary.error = Some(error);
+                    break;
                 }
             }
+
+            let has_newline = self.line.last() == Some(&b'\n');
+
+            if has_newline {
+                self.line.pop();
+            }
+
+            let blank = self.at_line_start && self.line.is_empty();
+
+            if self.config.squeeze_blank && blank && self.previous_line_blank {
+                continue;
+            }
+
+            self.previous_line_blank = blank;
+
+            let numbered = match &self.config.number_matching {
+                Some(pattern) => pattern.is_match(&self.line),
+                None => self.config.number_lines || (self.config.number_nonblank_lines && !blank),
+            };
+
+            if self.at_line_start && numbered {
+                write_number(self.line_number, &self.config, &mut self.output)?;
+                self.line_number += self.config.line_increment;
+            }
+
+            match self.line.split_last() {
+                Some((b'\r', rest)) if self.show_ends && has_newline => {
+                    show_bytes(
+                        rest,
+                        self.show_nonprinting,
+                        self.show_tabs,
+                        &mut self.output,
+                    );
+                    self.output.extend_from_slice(b"^M");
+                }
+                _ => show_bytes(
+                    &self.line,
+                    self.show_nonprinting,
+                    self.show_tabs,
+                    &mut self.output,
+                ),
+            }
+
+            if has_newline {
+                if self.show_ends {
+                    self.output.push(b'$');
+                }
+
+                self.output.push(b'\n');
+                summary.lines += 1;
+            }
+
+            self.at_line_start = has_newline;
+            output.write_all(&self.output)?;
+            summary.bytes += self.output.len() as u64;
+        }
+
+        Ok(summary)
+    }
+}
+
+pub fn open(filename: &str, decompress: bool) -> io::Result<Box<dyn BufRead>> {
+    let file: Box<dyn BufRead> = match filename {
+        "-" => Box::new(BufReader::with_capacity(BUFFER_SIZE, io::stdin())),
+        _ => Box::new(BufReader::with_capacity(
+            BUFFER_SIZE,
+            std::fs::File::open(filename)?,
+        )),
+    };
+
+    if decompress {
+        decoder(file)
+    } else {
+        Ok(file)
+    }
+}
+
+fn decoder(mut file: Box<dyn BufRead>) -> io::Result<Box<dyn BufRead>> {
+    let magic = file.fill_buf()?;
+
+    let decoder: Box<dyn Read> = if magic.starts_with(GZIP_MAGIC) {
+        Box::new(flate2::bufread::MultiGzDecoder::new(file))
+    } else if magic.starts_with(BZIP2_MAGIC) {
+        Box::new(bzip2::bufread::MultiBzDecoder::new(file))
+    } else if magic.starts_with(XZ_MAGIC) {
+        Box::new(lzma_rust2::XzReader::new(file, true))
+    } else if magic.starts_with(ZSTD_MAGIC) {
+        Box::new(ruzstd::decoding::StreamingDecoder::new(file).map_err(io::Error::other)?)
+    } else {
+        return Ok(file);
+    };
+
+    Ok(Box::new(BufReader::with_capacity(BUFFER_SIZE, decoder)))
+}
+
+fn copy(mut input: impl BufRead, mut output: impl Write) -> io::Result<Summary> {
+    let mut summary = Summary::default();
+
+    loop {
+        let buffer = match input.fill_buf() {
+            Ok(buffer) => buffer,
             Err(error) => {
-                eprintln!("{file}: {error}");
+                summary.error = Some(error);
+                break;
             }
+        };
+
+        if buffer.is_empty() {
+            break;
         }
+
+        output.write_all(buffer)?;
+        summary.bytes += buffer.len() as u64;
+        summary.lines += buffer.iter().filter(|&&byte| byte == b'\n').count() as u64;
+
+        let length = buffer.len();
+        input.consume(length);
+    }
+
+    Ok(summary)
+}
+
+fn write_number(number: i64, config: &Config, output: &mut Vec<u8>) -> io::Result<()> {
+    let width = config.number_width;
+
+    match config.number_format {
+        NumberFormat::Ln => write!(output, "{number:<width$}")?,
+        NumberFormat::Rn => write!(output, "{number:>width$}")?,
+        NumberFormat::Rz => write!(output, "{number:0width$}")?,
     }
 
+    output.extend_from_slice(config.number_separator.as_bytes());
+
     Ok(())
 }
 
-fn open(filename: &str) -> Result<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(std::io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(std::fs::File::open(filename)?))),
+fn show_bytes(line: &[u8], show_nonprinting: bool, show_tabs: bool, output: &mut Vec<u8>) {
+    for &byte in line {
+        match byte {
+            b'\t' if show_tabs => output.extend_from_slice(b"^I"),
+            b'\t' => output.push(byte),
+            _ if !show_nonprinting => output.push(byte),
+            0..=31 => output.extend_from_slice(&[b'^', byte + 64]),
+            32..=126 => output.push(byte),
+            127 => output.extend_from_slice(b"^?"),
+            128..=159 => output.extend_from_slice(&[b'M', b'-', b'^', byte - 128 + 64]),
+            160..=254 => output.extend_from_slice(&[b'M', b'-', byte - 128]),
+            255 => output.extend_from_slice(b"M-^?"),
+        }
     }
 }
 
@@ -56,4 +305,187 @@ pub struct Config {
         help = "Number nonempty output lines, overrides -n"
     )]
     pub number_nonblank_lines: bool,
+
+    #[arg(
+        long = "number-matching",
+        value_name = "PATTERN",
+        help = "Number only lines matching PATTERN"
+    )]
+    pub number_matching: Option<regex::bytes::Regex>,
+
+    #[arg(
+        long = "number-width",
+        default_value = "6",
+        help = "Use NUMBER_WIDTH columns for line numbers"
+    )]
+    pub number_width: usize,
+
+    #[arg(
+        long = "number-format",
+        value_enum,
+        default_value = "rn",
+        help = "Insert line numbers left aligned (ln), right aligned (rn) or zero-padded (rz)"
+    )]
+    pub number_format: NumberFormat,
+
+    #[arg(
+        long = "number-separator",
+        default_value = "\t",
+        help = "Add NUMBER_SEPARATOR after line numbers"
+    )]
+    pub number_separator: String,
+
+    #[arg(
+        long = "starting-line-number",
+        default_value = "1",
+        allow_negative_numbers = true,
+        help = "First line number"
+    )]
+    pub starting_line_number: i64,
+
+    #[arg(
+        long = "line-increment",
+        default_value = "1",
+        allow_negative_numbers = true,
+        help = "Line number increment"
+    )]
+    pub line_increment: i64,
+
+    #[arg(
+        long = "restart-numbering",
+        help = "Restart line numbering at each file"
+    )]
+    pub restart_numbering: bool,
+
+    #[arg(
+        short = 's',
+        long = "squeeze-blank",
+        help = "Suppress repeated empty output lines"
+    )]
+    pub squeeze_blank: bool,
+
+    #[arg(
+        short = 'v',
+        long = "show-nonprinting",
+        help = "Use ^ and M- notation, except for LFD and TAB"
+    )]
+    pub show_nonprinting: bool,
+
+    #[arg(
+        short = 'E',
+        long = "show-ends",
+        help = "Display $ at end of each line"
+    )]
+    pub show_ends: bool,
+
+    #[arg(short = 'T', long = "show-tabs", help = "Display TAB characters as ^I")]
+    pub show_tabs: bool,
+
+    #[arg(short = 'A', long = "show-all", help = "Equivalent to -vET")]
+    pub show_all: bool,
+
+    #[arg(
+        short = 'z',
+        long = "decompress",
+        help = "Decompress gzip, bzip2, xz and zstd input"
+    )]
+    pub decompress: bool,
+
+    #[arg(long = "strict", help = "Stop at the first file that cannot be read")]
+    pub strict: bool,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, clap::ValueEnum, Debug)]
+pub enum NumberFormat {
+    Ln,
+    Rn,
+    Rz,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cat, Config};
+    use clap::Parser;
+    use pretty_assertions::assert_eq;
+    use std::io::{self, Cursor, Read};
+
+    fn cat(args: &[&str], inputs: &[&[u8]]) -> Vec<u8> {
+        let config = Config::try_parse_from([&["catr"], args].concat()).unwrap();
+        let mut cat = Cat::new(config);
+        let mut output = Vec::new();
+
+        for input in inputs {
+            let summary = cat.cat(Cursor::new(input), &mut output).unwrap();
+            assert!(summary.error.is_none());
+        }
+
+        output
+    }
+
+    struct FailingReader;
+
+    impl Read for FailingReader {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("read failed"))
+        }
+    }
+
+    #[test]
+    fn cat_copies_bytes_unchanged() {
+        let input = b"one\r\ntwo\n\x00\xff\nno newline";
+        let config = Config::try_parse_from(["catr"]).unwrap();
+        let mut output = Vec::new();
+
+        let summary = Cat::new(config).cat(&input[..], &mut output).unwrap();
+
+        assert_eq!(output, input);
+        assert_eq!(summary.bytes, input.len() as u64);
+        assert_eq!(summary.lines, 3);
+        assert!(summary.error.is_none());
+    }
+
+    #[test]
+    fn cat_numbers_across_inputs() {
+        let output = cat(&["-n"], &[b"a\nb\n", b"c\n"]);
+        assert_eq!(output, b"     1\ta\n     2\tb\n     3\tc\n");
+
+        let output = cat(&["-n", "--restart-numbering"], &[b"a\nb\n", b"c\n"]);
+        assert_eq!(output, b"     1\ta\n     2\tb\n     1\tc\n");
+    }
+
+    #[test]
+    fn cat_squeezes_across_inputs() {
+        let output = cat(&["-s", "-b"], &[b"a\n\n\n", b"\nb\n"]);
+        assert_eq!(output, b"     1\ta\n\n     2\tb\n");
+    }
+
+    #[test]
+    fn cat_shows_nonprinting() {
+        let output = cat(&["-A"], &[b"\t\x07\x7f\xe9\r\n"]);
+        assert_eq!(output, b"^I^G^?M-i^M$\n");
+    }
+
+    #[test]
+    fn cat_reports_summary() {
+        let config = Config::try_parse_from(["catr", "-b"]).unwrap();
+        let mut output = Vec::new();
+
+        let summary = Cat::new(config).cat(&b"a\n\nb"[..], &mut output).unwrap();
+
+        assert_eq!(output, b"     1\ta\n\n     2\tb");
+        assert_eq!(summary.bytes, output.len() as u64);
+        assert_eq!(summary.lines, 2);
+    }
+
+    #[test]
+    fn cat_records_read_errors() {
+        for args in [&["catr"][..], &["catr", "-n"][..]] {
+            let config = Config::try_parse_from(args).unwrap();
+            let input = io::BufReader::new(FailingReader);
+
+            let summary = Cat::new(config).cat(input, io::sink()).unwrap();
+
+            assert_eq!(summary.error.unwrap().to_string(), "read failed");
+        }
+    }
 }
@@ -0,0 +1,194 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+
+use regex::bytes::Regex;
+
+use crate::{open, Result, Summary, BUFFER_SIZE};
+
+pub fn run(config: Config) -> Result<()> {
+    let (separator, max_match) = if config.regex {
+        (Regex::new(&config.separator)?, None)
+    } else {
+        (
+            Regex::new(&regex::escape(&config.separator))?,
+            Some(config.separator.len()),
+        )
+    };
+
+    let mut stdout = BufWriter::with_capacity(BUFFER_SIZE, io::stdout().lock());
+    let mut failed_files = 0;
+
+    for filename in config.files.iter() {
+        let summary = match open_seekable(filename, config.decompress) {
+            Ok(file) => tac(file, &mut stdout, &separator, max_match, config.before)?,
+            Err(error) => Summary {
+                error: Some(error),
+                ..Summary::default()
+            },
+        };
+
+        if let Some(error) = summary.error {
+            stdout.flush()?;
+            eprintln!("{filename}: {error}");
+            failed_files += 1;
+        }
+    }
+
+    stdout.flush()?;
+
+    if failed_files > 0 {
+        return Err(format!(
+            "{failed_files} of {} file(s) could not be read",
+            config.files.len()
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+// `max_match` is the longest a separator can be, when that is known, so only
+// the newly read chunk and that much of what follows it need to be searched.
+pub fn tac(
+    mut input: impl Read + Seek,
+    mut output: impl Write,
+    separator: &Regex,
+    max_match: Option<usize>,
+    before: bool,
+) -> io::Result<Summary> {
+    let mut summary = Summary::default();
+
+    // What is still pending is `buffer[start..]`. Each chunk is read into the
+    // free space in front of it, so a long record is not copied again for
+    // every chunk.
+    let mut buffer = Vec::new();
+    let mut start = 0;
+
+    let mut position = match input.seek(SeekFrom::End(0)) {
+        Ok(position) => position,
+        Err(error) => {
+            summary.error = Some(error);
+            return Ok(summary);
+        }
+    };
+
+    while position > 0 {
+        let size = position.min(BUFFER_SIZE as u64) as usize;
+        position -= size as u64;
+
+        if start < size {
+            let len = buffer.len() - start;
+            let mut grown = vec![0; size + 2 * len];
+            grown[size + len..].copy_from_slice(&buffer[start..]);
+            buffer = grown;
+            start = size + len;
+        }
+
+        start -= size;
+
+        if let Err(error) = input
+            .seek(SeekFrom::Start(position))
+            .and_then(|_| input.read_exact(&mut buffer[start..start + size]))
+        {
+            summary.error = Some(error);
+            return Ok(summary);
+        }
+
+        let pending = &buffer[start..];
+        let searched = max_match.map_or(pending.len(), |len| pending.len().min(size + len));
+
+        // A match touching the start of the buffer may continue into the
+        // previous chunk, so it is only trusted once the whole input is read.
+        let boundaries: Vec<usize> = separator
+            .find_iter(&pending[..searched])
+            .filter(|m| !m.is_empty() && (m.start() > 0 || position == 0))
+            .map(|m| if before { m.start() } else { m.end() })
+            .collect();
+
+        let mut end = pending.len();
+
+        for &boundary in boundaries.iter().rev() {
+            write_record(&pending[boundary..end], &mut output, &mut summary)?;
+            end = boundary;
+        }
+
+        buffer.truncate(start + end);
+    }
+
+    write_record(&buffer[start..], &mut output, &mut summary)?;
+
+    Ok(summary)
+}
+
+fn open_seekable(filename: &str, decompress: bool) -> io::Result<File> {
+    if filename == "-" || decompress {
+        return spool(open(filename, decompress)?);
+    }
+
+    let file = File::open(filename)?;
+
+    if file.metadata()?.is_file() {
+        Ok(file)
+    } else {
+        spool(BufReader::new(file))
+    }
+}
+
+fn spool(mut input: impl Read) -> io::Result<File> {
+    let mut file = tempfile::tempfile()?;
+    io::copy(&mut input, &mut file)?;
+
+    Ok(file)
+}
+
+fn write_record(record: &[u8], output: &mut impl Write, summary: &mut Summary) -> io::Result<()> {
+    if !record.is_empty() {
+        output.write_all(record)?;
+        summary.bytes += record.len() as u64;
+        summary.lines += 1;
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, clap::Parser)]
+#[command(
+    name = "tacr",
+    version,
+    about = "A clone of tac written in Rust that concatenates and prints FILE(s) in reverse."
+)]
+pub struct Config {
+    #[arg(default_value = "-", help = "Input file(s)")]
+    pub files: Vec<String>,
+
+    #[arg(
+        short = 'b',
+        long = "before",
+        help = "Attach the separator before instead of after"
+    )]
+    pub before: bool,
+
+    #[arg(
+        short = 'r',
+        long = "regex",
+        help = "Interpret the separator as a regular expression"
+    )]
+    pub regex: bool,
+
+    #[arg(
+        short = 's',
+        long = "separator",
+        default_value = "\n",
+        allow_hyphen_values = true,
+        value_parser = clap::builder::NonEmptyStringValueParser::new(),
+        help = "Use STRING as the separator instead of newline"
+    )]
+    pub separator: String,
+
+    #[arg(
+        short = 'z',
+        long = "decompress",
+        help = "Decompress gzip, bzip2, xz and zstd input"
+    )]
+    pub decompress: bool,
+}
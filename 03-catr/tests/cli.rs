@@ -10,6 +10,10 @@ const EMPTY: &str = "tests/inputs/empty.txt";
 const FOX: &str = "tests/inputs/fox.txt";
 const SPIDERS: &str = "tests/inputs/spiders.txt";
 const BUSTLE: &str = "tests/inputs/the-bustle.txt";
+const BLANKS: &str = "tests/inputs/blanks.txt";
+const CRLF: &str = "tests/inputs/crlf.txt";
+const CORRUPT: &str = "tests/inputs/corrupt.txt.gz";
+const NONPRINTING: &str = "tests/inputs/nonprinting.txt";
 
 // --------------------------------------------------
 #[test]
@@ -46,11 +50,42 @@ fn skips_bad_file() -> Result<()> {
     Command::cargo_bin(PRG)?
         .arg(&bad)
         .assert()
-        .success()
+        .failure()
         .stderr(predicate::str::is_match(expected)?);
     Ok(())
 }
 
+// --------------------------------------------------
+#[test]
+fn continues_after_bad_file() -> Result<()> {
+    let bad = gen_bad_file();
+    let expected = fs::read_to_string("tests/expected/fox-spiders.txt.out")?;
+    Command::cargo_bin(PRG)?
+        .args([FOX, &bad, SPIDERS])
+        .assert()
+        .code(1)
+        .stdout(expected)
+        .stderr(predicate::str::contains(format!("{bad}: ")))
+        .stderr(predicate::str::contains("1 of 3 file(s) could not be read"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn strict_stops_at_bad_file() -> Result<()> {
+    let bad = gen_bad_file();
+    let expected = fs::read_to_string("tests/expected/fox.txt.out")?;
+    Command::cargo_bin(PRG)?
+        .args(["--strict", FOX, &bad, SPIDERS])
+        .assert()
+        .code(1)
+        .stdout(expected)
+        .stderr(predicate::str::contains(format!(
+            "stopped at the first error in {bad}"
+        )));
+    Ok(())
+}
+
 // --------------------------------------------------
 fn run(args: &[&str], expected_file: &str) -> Result<()> {
     let expected = fs::read_to_string(expected_file)?;
@@ -63,6 +98,16 @@ fn run(args: &[&str], expected_file: &str) -> Result<()> {
     Ok(())
 }
 
+// --------------------------------------------------
+fn run_bytes(args: &[&str], expected_file: &str) -> Result<()> {
+    let expected = fs::read(expected_file)?;
+    let output = Command::cargo_bin(PRG)?.args(args).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, expected);
+
+    Ok(())
+}
+
 // --------------------------------------------------
 fn run_stdin(
     input_file: &str,
@@ -201,3 +246,277 @@ fn all_n() -> Result<()> {
 fn all_b() -> Result<()> {
     run(&[FOX, SPIDERS, BUSTLE, "-b"], "tests/expected/all.b.out")
 }
+
+// --------------------------------------------------
+#[test]
+fn nonprinting() -> Result<()> {
+    run_bytes(&[NONPRINTING], "tests/expected/nonprinting.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_v() -> Result<()> {
+    run(&["-v", NONPRINTING], "tests/expected/nonprinting.txt.v.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_e() -> Result<()> {
+    run_bytes(&["-E", NONPRINTING], "tests/expected/nonprinting.txt.E.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_t() -> Result<()> {
+    run_bytes(&["-T", NONPRINTING], "tests/expected/nonprinting.txt.T.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_a() -> Result<()> {
+    run(&["-A", NONPRINTING], "tests/expected/nonprinting.txt.A.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_vet() -> Result<()> {
+    run(
+        &["-v", "-E", "-T", NONPRINTING],
+        "tests/expected/nonprinting.txt.A.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_n_a() -> Result<()> {
+    run(
+        &["-n", "-A", NONPRINTING],
+        "tests/expected/nonprinting.txt.nA.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_b_a() -> Result<()> {
+    run(
+        &["-b", "-A", NONPRINTING],
+        "tests/expected/nonprinting.txt.bA.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn blanks_s() -> Result<()> {
+    run(&["-s", BLANKS], "tests/expected/blanks.txt.s.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn blanks_s_n() -> Result<()> {
+    run(&["-s", "-n", BLANKS], "tests/expected/blanks.txt.sn.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn blanks_s_b() -> Result<()> {
+    run(
+        &["--squeeze-blank", "-b", BLANKS],
+        "tests/expected/blanks.txt.sb.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn blanks_twice_s_n() -> Result<()> {
+    run(
+        &["-s", "-n", BLANKS, BLANKS],
+        "tests/expected/blanks.txt.twice.sn.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn crlf() -> Result<()> {
+    run_bytes(&[CRLF], "tests/expected/crlf.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn crlf_stdin() -> Result<()> {
+    let input = fs::read(CRLF)?;
+    let output = Command::cargo_bin(PRG)?
+        .write_stdin(input.clone())
+        .output()?;
+    assert!(output.status.success());
+    assert_eq!(output.stdout, input);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn crlf_n() -> Result<()> {
+    run_bytes(&["-n", CRLF], "tests/expected/crlf.txt.n.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn crlf_e() -> Result<()> {
+    run_bytes(&["-E", CRLF], "tests/expected/crlf.txt.E.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn crlf_fox() -> Result<()> {
+    run_bytes(&[CRLF, FOX, CRLF], "tests/expected/crlf.txt.fox.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn crlf_fox_n() -> Result<()> {
+    run_bytes(
+        &["-n", CRLF, FOX, CRLF],
+        "tests/expected/crlf.txt.fox.n.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_gz() -> Result<()> {
+    run(
+        &["-z", "tests/inputs/the-bustle.txt.gz"],
+        "tests/expected/the-bustle.txt.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_bz2() -> Result<()> {
+    run(
+        &["-z", "tests/inputs/the-bustle.txt.bz2"],
+        "tests/expected/the-bustle.txt.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_xz() -> Result<()> {
+    run(
+        &["-z", "tests/inputs/the-bustle.txt.xz"],
+        "tests/expected/the-bustle.txt.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_zst() -> Result<()> {
+    run(
+        &["--decompress", "tests/inputs/the-bustle.txt.zst"],
+        "tests/expected/the-bustle.txt.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_gz_stdin_n() -> Result<()> {
+    let input = fs::read("tests/inputs/the-bustle.txt.gz")?;
+    let expected = fs::read_to_string("tests/expected/the-bustle.txt.n.out")?;
+    let output = Command::cargo_bin(PRG)?
+        .write_stdin(input)
+        .args(["-z", "-n", "-"])
+        .output()?;
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout)?, expected);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn multiple_gz_members() -> Result<()> {
+    run(
+        &["-z", "tests/inputs/fox-spiders.txt.gz"],
+        "tests/expected/fox-spiders.txt.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn decompress_plain_file() -> Result<()> {
+    run(&["-z", FOX], "tests/expected/fox.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn skips_corrupt_archive() -> Result<()> {
+    let expected = fs::read_to_string("tests/expected/fox.txt.out")?;
+    Command::cargo_bin(PRG)?
+        .args(["-z", CORRUPT, FOX])
+        .assert()
+        .failure()
+        .stdout(expected)
+        .stderr(predicate::str::starts_with(format!("{CORRUPT}: ")));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_number_format() -> Result<()> {
+    run(
+        &[
+            "-n",
+            "--number-format",
+            "rz",
+            "--number-width",
+            "3",
+            "--number-separator",
+            ": ",
+            "--starting-line-number",
+            "10",
+            "--line-increment",
+            "5",
+            BUSTLE,
+        ],
+        "tests/expected/the-bustle.txt.nl.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_number_format_ln() -> Result<()> {
+    run(
+        &["-n", "--number-format", "ln", BUSTLE],
+        "tests/expected/the-bustle.txt.ln.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_number_matching() -> Result<()> {
+    run(
+        &["--number-matching", "^The", BUSTLE],
+        "tests/expected/the-bustle.txt.matching.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn restart_numbering() -> Result<()> {
+    run(
+        &["-n", "--restart-numbering", FOX, SPIDERS],
+        "tests/expected/fox-spiders.txt.restart.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_number_matching() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--number-matching", "(", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("regex parse error"));
+
+    Ok(())
+}
@@ -0,0 +1,182 @@
+use anyhow::Result;
+use assert_cmd::Command;
+use predicates::prelude::*;
+use pretty_assertions::assert_eq;
+use std::fs;
+use std::io::Write;
+
+const PRG: &str = "tacr";
+const EMPTY: &str = "tests/inputs/empty.txt";
+const BUSTLE: &str = "tests/inputs/the-bustle.txt";
+const BLANKS: &str = "tests/inputs/blanks.txt";
+const CRLF: &str = "tests/inputs/crlf.txt";
+
+// --------------------------------------------------
+#[test]
+fn usage() -> Result<()> {
+    for flag in &["-h", "--help"] {
+        Command::cargo_bin(PRG)?
+            .arg(flag)
+            .assert()
+            .stdout(predicate::str::contains("Usage"));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+fn run(args: &[&str], expected_file: &str) -> Result<()> {
+    let expected = fs::read(expected_file)?;
+    let output = Command::cargo_bin(PRG)?.args(args).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, expected);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn empty() -> Result<()> {
+    run(&[EMPTY], "tests/expected/empty.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle() -> Result<()> {
+    run(&[BUSTLE], "tests/expected/the-bustle.txt.tac.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_stdin() -> Result<()> {
+    let input = fs::read(BUSTLE)?;
+    let expected = fs::read("tests/expected/the-bustle.txt.tac.out")?;
+    let output = Command::cargo_bin(PRG)?.write_stdin(input).output()?;
+    assert!(output.status.success());
+    assert_eq!(output.stdout, expected);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_gz() -> Result<()> {
+    run(
+        &["-z", "tests/inputs/the-bustle.txt.gz"],
+        "tests/expected/the-bustle.txt.tac.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_before() -> Result<()> {
+    run(&["-b", BUSTLE], "tests/expected/the-bustle.txt.tac.b.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_separator() -> Result<()> {
+    run(
+        &["-s", "e", BUSTLE],
+        "tests/expected/the-bustle.txt.tac.s.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn multiple_files() -> Result<()> {
+    run(&[CRLF, BLANKS], "tests/expected/crlf-blanks.txt.tac.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn regex_separator() -> Result<()> {
+    for (args, expected) in [
+        (&["-r", "-s", "-+"][..], "three--two----one--"),
+        (&["-r", "-b", "-s", "-+"][..], "------three--twoone"),
+    ] {
+        Command::cargo_bin(PRG)?
+            .args(args)
+            .write_stdin("one--two----three--")
+            .assert()
+            .success()
+            .stdout(expected);
+    }
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn larger_than_buffer() -> Result<()> {
+    let lines: Vec<String> = (1..=100_000).map(|n| n.to_string()).collect();
+    let mut file = tempfile::NamedTempFile::new()?;
+    writeln!(file, "{}", lines.join("\n"))?;
+
+    let mut expected = lines.iter().rev().cloned().collect::<Vec<_>>();
+    expected.push(String::new());
+
+    Command::cargo_bin(PRG)?
+        .arg(file.path())
+        .assert()
+        .success()
+        .stdout(expected.join("\n"));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn records_longer_than_buffer() -> Result<()> {
+    let records: Vec<String> = [3, 131_071, 131_072, 400_000, 1, 262_143]
+        .iter()
+        .enumerate()
+        .map(|(n, &len)| format!("{n}{}ab", "x".repeat(len)))
+        .collect();
+    let mut file = tempfile::NamedTempFile::new()?;
+    write!(file, "{}", records.concat())?;
+
+    let expected: String = records.iter().rev().cloned().collect();
+
+    Command::cargo_bin(PRG)?
+        .args(["-s", "ab"])
+        .arg(file.path())
+        .assert()
+        .success()
+        .stdout(expected);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn skips_bad_file() -> Result<()> {
+    let expected = fs::read_to_string("tests/expected/the-bustle.txt.tac.out")?;
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/missing.txt", BUSTLE])
+        .assert()
+        .code(1)
+        .stdout(expected)
+        .stderr(predicate::str::is_match(
+            "tests/inputs/missing.txt: .* [(]os error 2[)]",
+        )?);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn broken_pipe_is_quiet() -> Result<()> {
+    let script = format!(
+        "seq 1 300000 | {} | head -n 1",
+        assert_cmd::cargo::cargo_bin(PRG).display()
+    );
+
+    Command::new("sh")
+        .args(["-c", &script])
+        .assert()
+        .success()
+        .stdout("300000\n")
+        .stderr("");
+
+    Ok(())
+}
@@ -1,55 +1,330 @@
+use std::collections::VecDeque;
 use std::fs::File;
-use std::io::{BufRead, BufReader, Read};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::time::{Duration, Instant};
+
+use unicode_segmentation::UnicodeSegmentation;
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 pub fn run(config: Config) -> Result<()> {
     let number_of_files = config.files.len();
+    let print_headers = config.verbose || (number_of_files > 1 && !config.quiet);
+    let delimiter = if config.zero_terminated { b'\0' } else { b'\n' };
+    let mut failed = 0;
 
     for (index, filename) in config.files.iter().enumerate() {
-        match open_file(&filename) {
+        match open_file(filename, config.unbuffered) {
             Ok(mut file) => {
-                if number_of_files > 1 {
+                if print_headers {
                     println!("{}==> {} <==", if index != 0 { "\n" } else { "" }, filename);
                 }
 
-                match config.bytes {
-                    Some(b) => {
-                        let bytes: std::result::Result<Vec<_>, _> = file.bytes().take(b).collect();
-                        print!("{}", String::from_utf8_lossy(&bytes?));
-                    }
-                    None => {
-                        let lines = read_lines_from_file(&mut file, config.lines)?;
-                        print!("{}", lines);
+                let unread = match head(&mut file, &config, delimiter) {
+                    Ok(unread) => unread,
+                    Err(e) => {
+                        eprintln!("{filename}: {e}");
+                        failed += 1;
+                        continue;
                     }
+                };
+
+                if filename == "-" {
+                    // Leave a seekable stdin right after the printed data for the next reader
+                    let _ = file.seek(SeekFrom::Current(-(unread as i64)));
                 }
             }
-            Err(e) => eprintln!("{filename}: {e}"),
+            Err(e) => {
+                eprintln!("{filename}: {e}");
+                failed += 1;
+            }
         }
     }
 
+    if failed > 0 {
+        return Err(format!("{failed} file(s) could not be read").into());
+    }
+
     Ok(())
 }
 
-fn open_file(filename: &str) -> Result<Box<dyn BufRead>> {
+// On Unix stdin is read through a duplicate of its descriptor, so a seekable
+// stdin can be left right after the printed data. Elsewhere it is read as is.
+enum Input {
+    File(File),
+    #[cfg(not(unix))]
+    Stdin(io::Stdin),
+}
+
+impl Input {
+    fn is_file(&self) -> io::Result<bool> {
+        match self {
+            Input::File(file) => Ok(file.metadata()?.is_file()),
+            #[cfg(not(unix))]
+            Input::Stdin(_) => Ok(false),
+        }
+    }
+}
+
+impl Read for Input {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Input::File(file) => file.read(buf),
+            #[cfg(not(unix))]
+            Input::Stdin(stdin) => stdin.read(buf),
+        }
+    }
+}
+
+impl Seek for Input {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            Input::File(file) => file.seek(pos),
+            #[cfg(not(unix))]
+            Input::Stdin(_) => Err(io::ErrorKind::Unsupported.into()),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn open_stdin() -> Result<Input> {
+    use std::os::fd::AsFd;
+
+    Ok(Input::File(File::from(
+        io::stdin().as_fd().try_clone_to_owned()?,
+    )))
+}
+
+#[cfg(not(unix))]
+fn open_stdin() -> Result<Input> {
+    Ok(Input::Stdin(io::stdin()))
+}
+
+fn open_file(filename: &str, unbuffered: bool) -> Result<BufReader<Input>> {
     match filename {
-        "-" => Ok(Box::new(BufReader::new(std::io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+        "-" => {
+            let stdin = open_stdin()?;
+
+            if unbuffered {
+                Ok(BufReader::with_capacity(1, stdin))
+            } else {
+                Ok(BufReader::new(stdin))
+            }
+        }
+        _ => Ok(BufReader::new(Input::File(File::open(filename)?))),
+    }
+}
+
+fn head(file: &mut BufReader<Input>, config: &Config, delimiter: u8) -> Result<usize> {
+    let mut stdout = std::io::stdout();
+
+    if let Some(c) = config.chars {
+        return write_chars(file, c, config.graphemes, &mut stdout);
+    }
+
+    match config.bytes {
+        Some(Count::First(b)) => {
+            std::io::copy(&mut file.take(b as u64), &mut stdout)?;
+            Ok(0)
+        }
+        Some(Count::AllButLast(b)) => write_bytes_except_last(file, b, &mut stdout),
+        None => match config.lines {
+            Count::First(n) => {
+                // Only a regular file can still grow, the end of a pipe is final
+                let wait = config.wait && file.get_ref().is_file()?;
+                let deadline = config.timeout.map(|timeout| Instant::now() + timeout);
+
+                read_lines_from_file(file, n, delimiter, wait, deadline, &mut stdout)?;
+                Ok(0)
+            }
+            Count::AllButLast(n) => write_lines_except_last(file, n, delimiter, &mut stdout),
+        },
+    }
+}
+
+fn read_lines_from_file(
+    file: &mut impl BufRead,
+    number_of_lines: usize,
+    delimiter: u8,
+    wait: bool,
+    deadline: Option<Instant>,
+    output: &mut impl Write,
+) -> Result<()> {
+    let mut line = Vec::new();
+    let mut lines_read = 0;
+
+    while lines_read < number_of_lines {
+        let bytes_read = file.read_until(delimiter, &mut line)?;
+
+        if line.last() == Some(&delimiter) || (bytes_read > 0 && !wait) {
+            output.write_all(&line)?;
+            line.clear();
+            lines_read += 1;
+
+            if wait {
+                output.flush()?;
+            }
+        } else if bytes_read == 0 {
+            if !wait {
+                break;
+            }
+
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                output.write_all(&line)?;
+                output.flush()?;
+
+                return Err(
+                    format!("timed out after {lines_read} of {number_of_lines} line(s)").into(),
+                );
+            }
+
+            std::thread::sleep(POLL_INTERVAL);
+        }
     }
+
+    Ok(())
 }
 
-fn read_lines_from_file(file: &mut Box<dyn BufRead>, number_of_lines: usize) -> Result<String> {
-    let mut lines = String::new();
+fn write_lines_except_last(
+    file: &mut impl BufRead,
+    number_of_lines: usize,
+    delimiter: u8,
+    output: &mut impl Write,
+) -> Result<usize> {
+    let mut lines = VecDeque::new();
+    let mut line = Vec::new();
 
-    for _ in 0..number_of_lines {
-        let bytes_read = file.read_line(&mut lines)?;
+    loop {
+        let bytes_read = file.read_until(delimiter, &mut line)?;
 
         if bytes_read == 0 {
             break;
         }
+
+        lines.push_back(std::mem::take(&mut line));
+
+        if lines.len() > number_of_lines {
+            if let Some(line) = lines.pop_front() {
+                output.write_all(&line)?;
+            }
+        }
+    }
+
+    output.flush()?;
+
+    Ok(lines.iter().map(Vec::len).sum())
+}
+
+fn write_bytes_except_last(
+    file: &mut impl BufRead,
+    number_of_bytes: usize,
+    output: &mut impl Write,
+) -> Result<usize> {
+    let mut bytes = VecDeque::new();
+
+    loop {
+        let buffer = file.fill_buf()?;
+
+        if buffer.is_empty() {
+            break;
+        }
+
+        bytes.extend(buffer);
+
+        let length = buffer.len();
+        file.consume(length);
+
+        if bytes.len() > number_of_bytes {
+            let excess: Vec<u8> = bytes.drain(..bytes.len() - number_of_bytes).collect();
+            output.write_all(&excess)?;
+        }
     }
 
-    Ok(lines)
+    output.flush()?;
+
+    Ok(bytes.len())
+}
+
+fn write_chars(
+    file: &mut impl BufRead,
+    number_of_chars: usize,
+    graphemes: bool,
+    output: &mut impl Write,
+) -> Result<usize> {
+    let mut pending = Vec::new();
+    let mut remaining = number_of_chars;
+
+    while remaining > 0 {
+        let buffer = file.fill_buf()?;
+        let eof = buffer.is_empty();
+
+        pending.extend_from_slice(buffer);
+
+        let length = buffer.len();
+        file.consume(length);
+
+        let lengths = char_lengths(&pending, graphemes, eof);
+        let count = remaining.min(lengths.len());
+        let bytes = lengths[..count].iter().sum();
+
+        output.write_all(&pending[..bytes])?;
+        pending.drain(..bytes);
+        remaining -= count;
+
+        if eof {
+            break;
+        }
+    }
+
+    output.flush()?;
+
+    Ok(pending.len())
+}
+
+fn char_lengths(buffer: &[u8], graphemes: bool, eof: bool) -> Vec<usize> {
+    let mut lengths = Vec::new();
+    let mut rest = buffer;
+
+    while !rest.is_empty() {
+        let (valid, invalid) = match std::str::from_utf8(rest) {
+            Ok(text) => (text, None),
+            Err(e) => (
+                std::str::from_utf8(&rest[..e.valid_up_to()]).unwrap_or_default(),
+                e.error_len(),
+            ),
+        };
+
+        if graphemes {
+            let mut clusters: Vec<usize> = valid.graphemes(true).map(str::len).collect();
+
+            // The last cluster may still grow with the next read
+            if invalid.is_none() && !eof {
+                clusters.pop();
+            }
+
+            lengths.extend(clusters);
+        } else {
+            lengths.extend(valid.chars().map(char::len_utf8));
+        }
+
+        rest = &rest[valid.len()..];
+
+        match invalid {
+            Some(length) => {
+                lengths.push(length);
+                rest = &rest[length..];
+            }
+            None if eof && !rest.is_empty() => {
+                lengths.push(rest.len());
+                break;
+            }
+            None => break,
+        }
+    }
+
+    lengths
 }
 
 #[derive(clap::Parser, Debug)]
@@ -63,16 +338,153 @@ pub struct Config {
         long = "lines",
         default_value = "10",
         group = "option",
-        help = "The number of lines to print"
+        allow_hyphen_values = true,
+        value_parser = parse_count,
+        help = "The number of lines to print, or all but the last -NUM lines"
     )]
-    lines: usize,
+    lines: Count,
 
     #[arg(
         required = false,
         short = 'c',
         long = "bytes",
         group = "option",
-        help = "The number of bytes to print"
+        allow_hyphen_values = true,
+        value_parser = parse_count,
+        help = "The number of bytes to print, or all but the last -NUM bytes"
     )]
-    bytes: Option<usize>,
+    bytes: Option<Count>,
+
+    #[arg(
+        short = 'm',
+        long = "chars",
+        group = "option",
+        help = "The number of characters to print"
+    )]
+    chars: Option<usize>,
+
+    #[arg(
+        long = "graphemes",
+        requires = "chars",
+        help = "Count grapheme clusters instead of characters with -m"
+    )]
+    graphemes: bool,
+
+    #[arg(
+        short = 'q',
+        long = "quiet",
+        visible_alias = "silent",
+        overrides_with = "verbose",
+        help = "Never print headers giving file names"
+    )]
+    quiet: bool,
+
+    #[arg(
+        short = 'v',
+        long = "verbose",
+        overrides_with = "quiet",
+        help = "Always print headers giving file names"
+    )]
+    verbose: bool,
+
+    #[arg(
+        short = 'z',
+        long = "zero-terminated",
+        help = "Line delimiter is NUL, not newline"
+    )]
+    zero_terminated: bool,
+
+    #[arg(
+        short = 'u',
+        long = "unbuffered",
+        help = "Read standard input without consuming past the printed data"
+    )]
+    unbuffered: bool,
+
+    #[arg(
+        long = "wait",
+        conflicts_with_all = ["bytes", "chars"],
+        help = "Keep reading a growing input until the requested number of lines has arrived"
+    )]
+    wait: bool,
+
+    #[arg(
+        long = "timeout",
+        requires = "wait",
+        value_name = "SECONDS",
+        value_parser = parse_timeout,
+        help = "Stop waiting for more lines after SECONDS"
+    )]
+    timeout: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Count {
+    First(usize),
+    AllButLast(usize),
+}
+
+fn parse_count(input: &str) -> std::result::Result<Count, String> {
+    let (value, all_but_last) = match input.strip_prefix('-') {
+        Some(value) => (value, true),
+        None => (input, false),
+    };
+
+    let (digits, suffix) = value.split_at(
+        value
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(value.len()),
+    );
+
+    let number = match digits.parse::<usize>() {
+        Ok(number) => number,
+        Err(e) if *e.kind() == std::num::IntErrorKind::PosOverflow => usize::MAX,
+        Err(_) => return Err("expected a number with an optional suffix".to_string()),
+    };
+
+    let multiplier = parse_suffix(suffix).ok_or_else(|| {
+        format!("invalid suffix '{suffix}', expected one of b, kB, K, KiB, MB, M, MiB, ...")
+    })?;
+
+    let count = number.saturating_mul(multiplier);
+
+    match all_but_last {
+        true => Ok(Count::AllButLast(count)),
+        false => Ok(Count::First(count)),
+    }
+}
+
+fn parse_suffix(suffix: &str) -> Option<usize> {
+    if suffix.is_empty() {
+        return Some(1);
+    } else if suffix == "b" {
+        return Some(512);
+    }
+
+    let (prefix, base) = if let Some(prefix) = suffix.strip_suffix("iB") {
+        (prefix, 1024_usize)
+    } else if let Some(prefix) = suffix.strip_suffix('B') {
+        (prefix, 1000)
+    } else {
+        (suffix, 1024)
+    };
+
+    let exponent = match prefix {
+        "k" if base == 1000 => 1,
+        "K" => 1,
+        "M" => 2,
+        "G" => 3,
+        "T" => 4,
+        "P" => 5,
+        "E" => 6,
+        _ => return None,
+    };
+
+    Some(base.saturating_pow(exponent))
+}
+
+fn parse_timeout(input: &str) -> std::result::Result<Duration, String> {
+    let seconds: f64 = input.parse().map_err(|e| format!("{e}"))?;
+
+    Duration::try_from_secs_f64(seconds).map_err(|e| format!("{e}"))
 }
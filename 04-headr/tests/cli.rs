@@ -12,6 +12,8 @@ const ONE: &str = "./tests/inputs/one.txt";
 const TWO: &str = "./tests/inputs/two.txt";
 const THREE: &str = "./tests/inputs/three.txt";
 const TWELVE: &str = "./tests/inputs/twelve.txt";
+const UNICODE: &str = "./tests/inputs/unicode.txt";
+const ZERO: &str = "./tests/inputs/zero.txt";
 
 // --------------------------------------------------
 fn random_string() -> String {
@@ -35,10 +37,11 @@ fn gen_bad_file() -> String {
 // --------------------------------------------------
 #[test]
 fn dies_bad_bytes() -> Result<()> {
-    let bad = random_string();
+    // A leading digit would be read as a number with a bad suffix
+    let bad = format!("x{}", random_string());
     let expected = format!(
         "invalid value '{bad}' for \
-        '--bytes <BYTES>': invalid digit found in string"
+        '--bytes <BYTES>': expected a number with an optional suffix"
     );
 
     Command::cargo_bin(PRG)?
@@ -53,10 +56,11 @@ fn dies_bad_bytes() -> Result<()> {
 // --------------------------------------------------
 #[test]
 fn dies_bad_lines() -> Result<()> {
-    let bad = random_string();
+    // A leading digit would be read as a number with a bad suffix
+    let bad = format!("x{}", random_string());
     let expected = format!(
         "error: invalid value '{bad}' for \
-        '--lines <LINES>': invalid digit found in string"
+        '--lines <LINES>': expected a number with an optional suffix"
     );
     Command::cargo_bin(PRG)?
         .args(["-n", &bad, EMPTY])
@@ -82,6 +86,20 @@ fn dies_bytes_and_lines() -> Result<()> {
     Ok(())
 }
 
+// --------------------------------------------------
+#[test]
+fn dies_bad_suffix() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["-c", "1Q", EMPTY])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "invalid value '1Q' for '--bytes <BYTES>': invalid suffix 'Q'",
+        ));
+
+    Ok(())
+}
+
 // --------------------------------------------------
 #[test]
 fn skips_bad_file() -> Result<()> {
@@ -90,6 +108,7 @@ fn skips_bad_file() -> Result<()> {
     Command::cargo_bin(PRG)?
         .args([EMPTY, &bad, ONE])
         .assert()
+        .failure()
         .stderr(predicate::str::is_match(expected)?);
 
     Ok(())
@@ -110,6 +129,16 @@ fn run(args: &[&str], expected_file: &str) -> Result<()> {
     Ok(())
 }
 
+// --------------------------------------------------
+fn run_bytes(args: &[&str], expected_file: &str) -> Result<()> {
+    let expected = fs::read(expected_file)?;
+    let output = Command::cargo_bin(PRG)?.args(args).output().expect("fail");
+    assert!(output.status.success());
+    assert_eq!(output.stdout, expected);
+
+    Ok(())
+}
+
 // --------------------------------------------------
 fn run_stdin(
     args: &[&str],
@@ -423,3 +452,323 @@ fn multiple_files_c4() -> Result<()> {
         "tests/expected/all.c4.out",
     )
 }
+
+// --------------------------------------------------
+#[test]
+fn twelve_n_minus2() -> Result<()> {
+    run(&[TWELVE, "-n", "-2"], "tests/expected/twelve.txt.n-2.out")
+}
+
+#[test]
+fn twelve_n_minus2_stdin() -> Result<()> {
+    run_stdin(&["-n", "-2"], TWELVE, "tests/expected/twelve.txt.n-2.out")
+}
+
+#[test]
+fn twelve_n_minus20() -> Result<()> {
+    run(&[TWELVE, "-n", "-20"], "tests/expected/twelve.txt.n-20.out")
+}
+
+#[test]
+fn twelve_c_minus4() -> Result<()> {
+    run(&[TWELVE, "-c", "-4"], "tests/expected/twelve.txt.c-4.out")
+}
+
+#[test]
+fn twelve_c_minus4_stdin() -> Result<()> {
+    run_stdin(&["-c", "-4"], TWELVE, "tests/expected/twelve.txt.c-4.out")
+}
+
+#[test]
+fn one_c_minus25() -> Result<()> {
+    run(&[ONE, "--bytes=-25"], "tests/expected/one.txt.c-25.out")
+}
+
+#[test]
+fn multiple_files_n_minus1() -> Result<()> {
+    run(
+        &["-n", "-1", EMPTY, ONE, TWO, THREE, TWELVE],
+        "tests/expected/all.n-1.out",
+    )
+}
+
+#[test]
+fn multiple_files_c_minus3() -> Result<()> {
+    run(
+        &["-c", "-3", EMPTY, ONE, TWO, THREE, TWELVE],
+        "tests/expected/all.c-3.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn unicode_c5() -> Result<()> {
+    run_bytes(&[UNICODE, "-c", "5"], "tests/expected/unicode.txt.c5.out")
+}
+
+#[test]
+fn unicode_c36() -> Result<()> {
+    run_bytes(&[UNICODE, "-c", "36"], "tests/expected/unicode.txt.c36.out")
+}
+
+#[test]
+fn unicode_m5() -> Result<()> {
+    run_bytes(&[UNICODE, "-m", "5"], "tests/expected/unicode.txt.m5.out")
+}
+
+#[test]
+fn unicode_m21() -> Result<()> {
+    run_bytes(
+        &[UNICODE, "--chars", "21"],
+        "tests/expected/unicode.txt.m21.out",
+    )
+}
+
+#[test]
+fn unicode_m4_graphemes() -> Result<()> {
+    run_bytes(
+        &[UNICODE, "-m", "4", "--graphemes"],
+        "tests/expected/unicode.txt.m4.graphemes.out",
+    )
+}
+
+#[test]
+fn unicode_m14_graphemes() -> Result<()> {
+    run_bytes(
+        &[UNICODE, "-m", "14", "--graphemes"],
+        "tests/expected/unicode.txt.m14.graphemes.out",
+    )
+}
+
+#[test]
+fn dies_chars_and_bytes() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["-m", "1", "-c", "2"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn size_suffixes() -> Result<()> {
+    let input = "x".repeat(3000);
+
+    for (count, expected) in [
+        ("1b", 512),
+        ("2kB", 2000),
+        ("1K", 1024),
+        ("1KiB", 1024),
+        ("1MB", 3000),
+        ("-1K", 1976),
+        ("-2b", 1976),
+    ] {
+        let output = Command::cargo_bin(PRG)?
+            .args(["-c", count])
+            .write_stdin(input.clone())
+            .output()?;
+        assert!(output.status.success());
+        assert_eq!(output.stdout.len(), expected);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn size_suffixes_lines() -> Result<()> {
+    let input: String = (1..=2000).map(|n| format!("{n}\n")).collect();
+
+    for (count, expected) in [("1K", 1024), ("-1K", 976), ("1b", 512)] {
+        let output = Command::cargo_bin(PRG)?
+            .args(["-n", count])
+            .write_stdin(input.clone())
+            .output()?;
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8(output.stdout)?.lines().count(), expected);
+    }
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn multiple_files_quiet() -> Result<()> {
+    run(
+        &["-q", "-n", "2", ONE, TWO, THREE],
+        "tests/expected/all.n2.q.out",
+    )
+}
+
+#[test]
+fn one_verbose() -> Result<()> {
+    run(&["-v", ONE], "tests/expected/one.txt.v.out")
+}
+
+#[test]
+fn one_quiet_then_verbose() -> Result<()> {
+    run(&["-q", "-v", ONE], "tests/expected/one.txt.v.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn zero_terminated_n2() -> Result<()> {
+    run_bytes(&["-z", "-n", "2", ZERO], "tests/expected/zero.txt.z.n2.out")
+}
+
+#[test]
+fn zero_terminated_n_minus1() -> Result<()> {
+    run_bytes(
+        &["--zero-terminated", "-n", "-1", ZERO],
+        "tests/expected/zero.txt.z.n-1.out",
+    )
+}
+
+// --------------------------------------------------
+fn run_shell(script: &str, args: &[&str], input_file: &str) -> Result<()> {
+    let output = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(script)
+        .arg(assert_cmd::cargo::cargo_bin(PRG))
+        .arg(input_file)
+        .args(args)
+        .output()?;
+    assert!(output.status.success());
+    assert_eq!(output.stdout, fs::read(input_file)?);
+
+    Ok(())
+}
+
+#[test]
+fn leaves_seekable_stdin_after_output() -> Result<()> {
+    let script = r#"f=$1; shift; { "$0" "$@"; cat; } < "$f""#;
+
+    for args in [
+        &["-n", "2"][..],
+        &["-n", "-3"][..],
+        &["-c", "5"][..],
+        &["-c", "-5"][..],
+        &["-m", "3"][..],
+    ] {
+        run_shell(script, args, TWELVE)?;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn unbuffered_leaves_pipe_after_output() -> Result<()> {
+    let script = r#"f=$1; shift; cat "$f" | { "$0" -u "$@"; cat; }"#;
+
+    for args in [&["-n", "2"][..], &["-c", "5"][..], &["-m", "3"][..]] {
+        run_shell(script, args, TWELVE)?;
+        run_shell(script, args, UNICODE)?;
+    }
+
+    Ok(())
+}
+
+// --------------------------------------------------
+fn append_later(
+    mut file: tempfile::NamedTempFile,
+    chunks: &'static [&'static str],
+) -> std::thread::JoinHandle<std::io::Result<tempfile::NamedTempFile>> {
+    std::thread::spawn(move || {
+        for chunk in chunks {
+            std::thread::sleep(std::time::Duration::from_millis(300));
+            file.write_all(chunk.as_bytes())?;
+            file.flush()?;
+        }
+
+        Ok(file)
+    })
+}
+
+#[test]
+fn wait_for_growing_file() -> Result<()> {
+    let mut file = tempfile::NamedTempFile::new()?;
+    writeln!(file, "one")?;
+    let path = file.path().to_path_buf();
+    let writer = append_later(file, &["two\nthr", "ee\nfour\n"]);
+
+    Command::cargo_bin(PRG)?
+        .args(["-n", "3", "--wait", "--timeout", "10"])
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout("one\ntwo\nthree\n");
+
+    writer.join().unwrap()?;
+
+    Ok(())
+}
+
+#[test]
+fn wait_for_multiple_files() -> Result<()> {
+    let first = tempfile::NamedTempFile::new()?;
+    let second = tempfile::NamedTempFile::new()?;
+    let paths = [first.path().to_path_buf(), second.path().to_path_buf()];
+    let first_writer = append_later(first, &["one\n", "two\n"]);
+    let second_writer = append_later(second, &["three\n"]);
+
+    let expected = format!(
+        "==> {} <==\none\ntwo\n\n==> {} <==\nthree\n",
+        paths[0].display(),
+        paths[1].display()
+    );
+
+    Command::cargo_bin(PRG)?
+        .args(["-n", "1K", "--wait", "--timeout", "1"])
+        .args(&paths)
+        .assert()
+        .failure()
+        .stdout(expected)
+        .stderr(predicate::str::contains(
+            "timed out after 2 of 1024 line(s)",
+        ))
+        .stderr(predicate::str::contains(
+            "timed out after 1 of 1024 line(s)",
+        ));
+
+    first_writer.join().unwrap()?;
+    second_writer.join().unwrap()?;
+
+    Ok(())
+}
+
+#[test]
+fn wait_stops_at_end_of_pipe() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["-n", "3", "--wait"])
+        .write_stdin("a\n")
+        .timeout(std::time::Duration::from_secs(10))
+        .assert()
+        .success()
+        .stdout("a\n");
+
+    Ok(())
+}
+
+#[test]
+fn dies_read_error() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args([EMPTY, "tests/inputs", ONE])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(format!("==> {ONE} <==")))
+        .stderr(predicate::str::contains("tests/inputs: Is a directory"));
+
+    Ok(())
+}
+
+#[test]
+fn dies_wait_with_bytes() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--wait", "-c", "1", EMPTY])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+
+    Ok(())
+}
@@ -1,135 +1,566 @@
-use std::io::{BufRead, BufReader};
+pub mod stats;
 
-type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::io::{self, ErrorKind, Read};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar;
 
-pub fn run(config: Config) -> Result<()> {
-    let mut total_num_of_lines = 0;
-    let mut total_num_of_words = 0;
-    let mut total_num_of_chars = 0;
-    let mut total_num_of_bytes = 0;
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const BUFFER_SIZE: usize = 128 * 1024;
+
+pub fn run(mut config: Config) -> Result<()> {
+    if config.recursive {
+        return stats::run(&config);
+    }
+
+    let mut failed = 0;
+
+    if let Some(list) = &config.files0_from {
+        (config.files, failed) = read_files0(list)?;
+    }
+
+    let mut total = FileInfo::default();
+    let mut printer = Printer::new(&config)?;
 
     let num_of_files = config.files.len();
+    let next_file = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
 
-    for filename in config.files {
-        match open_file(&filename) {
-            Ok(mut file) => {
-                let (num_of_lines, num_of_words, num_of_chars, num_of_bytes) = count(&mut file)?;
+    thread::scope(|scope| -> Result<()> {
+        for _ in 0..config.jobs.min(num_of_files) {
+            let sender = sender.clone();
+            let next_file = &next_file;
+            let config = &config;
 
-                total_num_of_lines += num_of_lines;
-                total_num_of_words += num_of_words;
-                total_num_of_chars += num_of_chars;
-                total_num_of_bytes += num_of_bytes;
+            scope.spawn(move || loop {
+                let index = next_file.fetch_add(1, Ordering::Relaxed);
 
-                let num_of_lines = if config.lines {
-                    format!("{:8}", num_of_lines)
-                } else {
-                    "".to_string()
+                let Some(filename) = config.files.get(index) else {
+                    break;
                 };
 
-                let num_of_words = if config.words {
-                    format!("{:8}", num_of_words)
-                } else {
-                    "".to_string()
-                };
+                if sender.send((index, count_file(filename, config))).is_err() {
+                    break;
+                }
+            });
+        }
 
-                let num_of_chars = if config.chars {
-                    format!("{:8}", num_of_chars)
-                } else {
-                    "".to_string()
-                };
+        drop(sender);
 
-                let num_of_bytes = if config.bytes {
-                    format!("{:8}", num_of_bytes)
-                } else {
-                    "".to_string()
+        // Workers finish in any order, so results wait here until every file
+        // before them has been printed.
+        let mut finished = BTreeMap::new();
+        let mut next_to_print = 0;
+
+        for (index, result) in receiver {
+            finished.insert(index, result);
+
+            while let Some(result) = finished.remove(&next_to_print) {
+                let filename = &config.files[next_to_print];
+
+                let result = match result {
+                    Ok(counted) => {
+                        if counted.is_err() {
+                            failed += 1;
+                        }
+
+                        counted
+                    }
+                    Err(error) => Err(error),
                 };
 
-                let filename = if filename != "-" {
-                    format!(" {}", filename)
+                match &result {
+                    Ok(info) => {
+                        total.num_lines += info.num_lines;
+                        total.num_words += info.num_words;
+                        total.num_chars += info.num_chars;
+                        total.num_bytes += info.num_bytes;
+                        total.max_line_length = total.max_line_length.max(info.max_line_length);
+                    }
+                    Err(error) => eprintln!("{filename}: {error}"),
+                }
+
+                if config.total != Total::Only {
+                    printer.print(filename, &result, &config)?;
+                }
+
+                next_to_print += 1;
+            }
+        }
+
+        Ok(())
+    })?;
+
+    let print_total = match config.total {
+        Total::Auto => num_of_files > 1,
+        Total::Always | Total::Only => true,
+        Total::Never => false,
+    };
+
+    if print_total {
+        printer.print_total(total, &config)?;
+    }
+
+    printer.finish()?;
+
+    if failed > 0 {
+        return Err(format!("{failed} file(s) could not be counted").into());
+    }
+
+    Ok(())
+}
+
+enum Printer {
+    Text(Vec<(Vec<usize>, String)>),
+    Json,
+    Delimited(Box<csv::Writer<io::Stdout>>),
+}
+
+impl Printer {
+    fn new(config: &Config) -> Result<Printer> {
+        let delimiter = match config.format {
+            Format::Text => return Ok(Printer::Text(vec![])),
+            Format::Json => return Ok(Printer::Json),
+            Format::Csv => b',',
+            Format::Tsv => b'\t',
+        };
+
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(delimiter)
+            .from_writer(io::stdout());
+
+        let counters = selected_counts(&FileInfo::default(), config);
+        let mut header = vec!["name"];
+        header.extend(counters.iter().map(|(counter, _)| *counter));
+        header.push("error");
+        writer.write_record(header)?;
+
+        Ok(Printer::Delimited(Box::new(writer)))
+    }
+
+    fn print(&mut self, name: &str, result: &Result<FileInfo>, config: &Config) -> Result<()> {
+        match self {
+            Printer::Text(rows) => {
+                if let Ok(info) = result {
+                    let name = if name != "-" {
+                        format!(" {}", name)
+                    } else {
+                        "".to_string()
+                    };
+
+                    rows.push((values(info, config), name));
+                }
+            }
+            Printer::Json => {
+                let mut record = serde_json::Map::new();
+                record.insert("name".to_string(), name.into());
+
+                match result {
+                    Ok(info) => {
+                        for (counter, value) in selected_counts(info, config) {
+                            record.insert(counter.to_string(), value.into());
+                        }
+                    }
+                    Err(error) => {
+                        record.insert("error".to_string(), error.to_string().into());
+                    }
+                }
+
+                println!("{}", serde_json::Value::Object(record))
+            }
+            Printer::Delimited(writer) => {
+                let mut record = vec![name.to_string()];
+
+                match result {
+                    Ok(info) => {
+                        for (_, value) in selected_counts(info, config) {
+                            record.push(value.to_string());
+                        }
+                        record.push("".to_string());
+                    }
+                    Err(error) => {
+                        let counters = selected_counts(&FileInfo::default(), config);
+                        record.extend(counters.iter().map(|_| "".to_string()));
+                        record.push(error.to_string());
+                    }
+                }
+
+                writer.write_record(record)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn print_total(&mut self, total: FileInfo, config: &Config) -> Result<()> {
+        match self {
+            Printer::Text(rows) => {
+                let name = if config.total != Total::Only {
+                    " total".to_string()
                 } else {
                     "".to_string()
                 };
 
-                println!(
-                    "{}{}{}{}{}",
-                    num_of_lines, num_of_words, num_of_chars, num_of_bytes, filename
-                )
+                rows.push((values(&total, config), name));
+                Ok(())
             }
-            Err(error) => {
-                eprintln!("{filename}: {error}");
+            _ => self.print("total", &Ok(total), config),
+        }
+    }
+
+    // Text rows are only printed at the end, once the widest number across all
+    // of them is known, so the columns line up the way they do in GNU wc.
+    fn finish(self) -> Result<()> {
+        match self {
+            Printer::Text(rows) => {
+                let width = rows
+                    .iter()
+                    .flat_map(|(values, _)| values)
+                    .max()
+                    .map_or(1, |value| value.to_string().len());
+
+                for (values, name) in rows {
+                    let values: Vec<_> = values
+                        .iter()
+                        .map(|value| format!("{:width$}", value))
+                        .collect();
+
+                    println!("{}{}", values.join(" "), name);
+                }
             }
+            Printer::Json => {}
+            Printer::Delimited(mut writer) => writer.flush()?,
         }
+
+        Ok(())
     }
+}
 
-    if num_of_files > 1 {
-        let total_num_of_lines = if config.lines {
-            format!("{:8}", total_num_of_lines)
-        } else {
-            "".to_string()
-        };
+fn selected_counts(info: &FileInfo, config: &Config) -> Vec<(&'static str, usize)> {
+    let mut counts = vec![];
 
-        let total_num_of_words = if config.words {
-            format!("{:8}", total_num_of_words)
-        } else {
-            "".to_string()
-        };
+    if config.lines {
+        counts.push(("lines", info.num_lines));
+    }
 
-        let total_num_of_chars = if config.chars {
-            format!("{:8}", total_num_of_chars)
-        } else {
-            "".to_string()
-        };
+    if config.words {
+        counts.push(("words", info.num_words));
+    }
 
-        let total_num_of_bytes = if config.bytes {
-            format!("{:8}", total_num_of_bytes)
-        } else {
-            "".to_string()
-        };
+    if config.chars {
+        counts.push(("chars", info.num_chars));
+    }
 
-        println!(
-            "{}{}{}{} {}",
-            total_num_of_lines, total_num_of_words, total_num_of_chars, total_num_of_bytes, "total"
-        )
+    if config.bytes {
+        counts.push(("bytes", info.num_bytes));
     }
 
-    Ok(())
+    if config.max_line_length {
+        counts.push(("max_line_length", info.max_line_length));
+    }
+
+    counts
+}
+
+fn values(info: &FileInfo, config: &Config) -> Vec<usize> {
+    selected_counts(info, config)
+        .iter()
+        .map(|(_, value)| *value)
+        .collect()
 }
 
-fn open_file(filename: &str) -> Result<Box<dyn BufRead>> {
+// Reads the NUL-separated file names in `list`, the way `find -print0` writes
+// them. Returns the usable names along with how many were not usable; those
+// are reported as they are found, and their count makes the run fail.
+fn read_files0(list: &str) -> Result<(Vec<String>, usize)> {
+    let mut names = vec![];
+
+    let mut file = open_file(list).map_err(|e| format!("{list}: {e}"))?;
+    file.read_to_end(&mut names)
+        .map_err(|e| format!("{list}: {e}"))?;
+
+    if names.last() == Some(&0) {
+        names.pop();
+    }
+
+    let mut files = vec![];
+    let mut invalid = 0;
+
+    if names.is_empty() {
+        return Ok((files, invalid));
+    }
+
+    for (number, name) in names.split(|&byte| byte == 0).enumerate() {
+        let number = number + 1;
+
+        match String::from_utf8(name.to_vec()) {
+            Ok(name) if name.is_empty() => {
+                eprintln!("{list}:{number}: invalid zero-length file name");
+                invalid += 1;
+            }
+            Ok(name) if name == "-" && list == "-" => {
+                eprintln!("when reading file names from stdin, no file name of '-' allowed");
+                invalid += 1;
+            }
+            Ok(name) => files.push(name),
+            Err(_) => {
+                eprintln!("{list}:{number}: file name is not valid UTF-8");
+                invalid += 1;
+            }
+        }
+    }
+
+    Ok((files, invalid))
+}
+
+fn open_file(filename: &str) -> Result<Box<dyn Read>> {
     match filename {
-        "-" => Ok(Box::new(BufReader::new(std::io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(std::fs::File::open(&filename)?))),
+        "-" => Ok(Box::new(std::io::stdin())),
+        _ => Ok(Box::new(std::fs::File::open(filename)?)),
     }
 }
 
-fn count(mut file: impl BufRead) -> Result<(usize, usize, usize, usize)> {
-    let mut number_of_lines = 0;
-    let mut number_of_words = 0;
-    let mut number_of_chars = 0;
-    let mut number_of_bytes = 0;
+// A file that cannot be opened is the outer error, and is only reported, as
+// it always was. An error while reading one is the inner error, and makes the
+// whole run fail.
+fn count_file(filename: &str, config: &Config) -> Result<Result<FileInfo>> {
+    let bytes_only =
+        config.bytes && !config.lines && !config.words && !config.chars && !config.max_line_length;
+
+    if bytes_only && filename != "-" {
+        let file = std::fs::File::open(filename)?;
+
+        return Ok(file.metadata().map_err(Into::into).and_then(|metadata| {
+            // Files in /proc and /sys report a size of zero, so only a
+            // non-empty size is trusted.
+            if metadata.is_file() && metadata.len() > 0 {
+                return Ok(FileInfo {
+                    num_bytes: metadata.len() as usize,
+                    ..FileInfo::default()
+                });
+            }
+
+            count(file, config)
+        }));
+    }
+
+    Ok(count(open_file(filename)?, config))
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FileInfo {
+    pub num_lines: usize,
+    pub num_words: usize,
+    pub num_chars: usize,
+    pub num_bytes: usize,
+    pub max_line_length: usize,
+}
+
+pub fn count(file: impl Read, config: &Config) -> Result<FileInfo> {
+    count_with(file, config, None::<fn(&[u8])>)
+}
 
-    let mut line = String::new();
+// Counts like `count`, and also hands every line that ends in a newline to
+// `on_line`, without the newline, so the lines seen there are exactly the
+// ones counted.
+fn count_with(
+    mut file: impl Read,
+    config: &Config,
+    mut on_line: Option<impl FnMut(&[u8])>,
+) -> Result<FileInfo> {
+    let decode = config.words || config.chars || config.max_line_length;
+
+    let mut info = FileInfo::default();
+    let mut state = TextState::new(config);
+    let mut buffer = vec![0; BUFFER_SIZE];
+    let mut pending = 0;
+    let mut line = vec![];
 
     loop {
-        let bytes = file.read_line(&mut line)?;
+        let bytes_read = match file.read(&mut buffer[pending..]) {
+            Ok(bytes_read) => bytes_read,
+            Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error.into()),
+        };
+
+        let eof = bytes_read == 0;
+        let end = pending + bytes_read;
+        let chunk = &buffer[pending..end];
+
+        info.num_bytes += bytes_read;
+        info.num_lines += chunk.iter().filter(|&&byte| byte == b'\n').count();
+
+        if let Some(on_line) = on_line.as_mut() {
+            for piece in chunk.split_inclusive(|&byte| byte == b'\n') {
+                let Some((b'\n', text)) = piece.split_last() else {
+                    line.extend_from_slice(piece);
+                    continue;
+                };
+
+                if line.is_empty() {
+                    on_line(text);
+                } else {
+                    line.extend_from_slice(text);
+                    on_line(&line);
+                    line.clear();
+                }
+            }
+        }
+
+        if decode {
+            let consumed = state.update(&mut info, &buffer[..end], eof);
+            buffer.copy_within(consumed..end, 0);
+            pending = end - consumed;
+        }
 
-        if bytes == 0 {
+        if eof {
             break;
         }
+    }
+
+    state.finish(&mut info);
+
+    Ok(info)
+}
+
+#[derive(Debug)]
+struct TextState<'a> {
+    word_mode: WordMode,
+    word_regex: Option<&'a Regex>,
+    in_word: bool,
+    line_width: usize,
+    line: String,
+}
+
+impl<'a> TextState<'a> {
+    fn new(config: &'a Config) -> Self {
+        // The other word modes collect each line before splitting it, which
+        // is wasted work unless the words are actually printed.
+        let word_mode = if config.words {
+            config.word_mode
+        } else {
+            WordMode::Whitespace
+        };
+
+        TextState {
+            word_mode,
+            word_regex: config.word_regex.as_ref(),
+            in_word: false,
+            line_width: 0,
+            line: String::new(),
+        }
+    }
+
+    // Counts the words, characters and line widths in `chunk` and returns how
+    // many bytes were consumed. A multi-byte sequence cut off at the end of the
+    // chunk is left for the next call unless `eof` is set; any other invalid
+    // sequence counts as one character of no width that neither starts nor
+    // ends a word.
+    fn update(&mut self, info: &mut FileInfo, chunk: &[u8], eof: bool) -> usize {
+        let mut consumed = 0;
+        let mut utf8_chunks = chunk.utf8_chunks().peekable();
+
+        while let Some(utf8_chunk) = utf8_chunks.next() {
+            for c in utf8_chunk.valid().chars() {
+                self.push_char(info, c);
+            }
+
+            consumed += utf8_chunk.valid().len();
+
+            let invalid = utf8_chunk.invalid();
+
+            if invalid.is_empty() {
+                continue;
+            }
+
+            if !eof && utf8_chunks.peek().is_none() && is_incomplete(invalid) {
+                break;
+            }
+
+            info.num_chars += 1;
+            consumed += invalid.len();
+        }
+
+        consumed
+    }
+
+    // Line widths are measured the way `wc -L` does: tabs advance to the next
+    // multiple of 8, carriage returns and form feeds go back to the first
+    // column, and other control characters take no space.
+    fn push_char(&mut self, info: &mut FileInfo, c: char) {
+        info.num_chars += 1;
+
+        match c {
+            ' '..='~' => self.line_width += 1,
+            '\n' | '\r' | '\x0c' => {
+                info.max_line_length = info.max_line_length.max(self.line_width);
+                self.line_width = 0;
+            }
+            '\t' => self.line_width += 8 - self.line_width % 8,
+            c if c.is_control() => {}
+            c => self.line_width += c.width().unwrap_or(0),
+        }
 
-        number_of_lines += 1;
-        number_of_bytes += bytes;
-        number_of_words += line.split_whitespace().count();
-        number_of_chars += line.chars().count();
-        line.clear();
+        if self.word_mode != WordMode::Whitespace {
+            if c == '\n' {
+                self.count_line_words(info);
+            } else {
+                self.line.push(c);
+            }
+
+            return;
+        }
+
+        let is_whitespace = if c.is_ascii() {
+            matches!(c, '\t'..='\r' | ' ')
+        } else {
+            c.is_whitespace()
+        };
+
+        if is_whitespace {
+            self.in_word = false;
+        } else if !self.in_word {
+            info.num_words += 1;
+            self.in_word = true;
+        }
     }
 
-    Ok((
-        number_of_lines,
-        number_of_words,
-        number_of_chars,
-        number_of_bytes,
-    ))
+    fn count_line_words(&mut self, info: &mut FileInfo) {
+        info.num_words += match (self.word_mode, self.word_regex) {
+            (WordMode::Unicode, _) => self.line.unicode_words().count(),
+            (WordMode::Regex, Some(word_regex)) => word_regex
+                .find_iter(&self.line)
+                .filter(|word| !word.is_empty())
+                .count(),
+            _ => 0,
+        };
+
+        self.line.clear();
+    }
+
+    fn finish(&mut self, info: &mut FileInfo) {
+        self.count_line_words(info);
+        info.max_line_length = info.max_line_length.max(self.line_width);
+    }
+}
+
+// `Utf8Chunks` reports the longest valid prefix of a broken sequence, so a
+// lead byte followed by fewer continuation bytes than it announces can only
+// mean the sequence continues past the end of the chunk.
+fn is_incomplete(invalid: &[u8]) -> bool {
+    let width = match invalid[0] {
+        0xc2..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf4 => 4,
+        _ => 0,
+    };
+
+    invalid.len() < width
 }
 
 #[derive(clap::Parser, Debug)]
@@ -138,6 +569,14 @@ pub struct Config {
     #[arg(help = "Input file(s)", default_value = "-")]
     pub files: Vec<String>,
 
+    #[arg(
+        long = "files0-from",
+        value_name = "F",
+        help = "Read the input file names, separated by NUL characters, from F (- for standard input)",
+        conflicts_with = "files"
+    )]
+    pub files0_from: Option<String>,
+
     #[arg(short = 'l', long = "lines", help = "Print the line count")]
     pub lines: bool,
 
@@ -159,20 +598,198 @@ pub struct Config {
         group = "mc"
     )]
     pub bytes: bool,
+
+    #[arg(
+        short = 'L',
+        long = "max-line-length",
+        help = "Print the display width of the longest line"
+    )]
+    pub max_line_length: bool,
+
+    #[arg(
+        short = 'j',
+        long = "jobs",
+        help = "Number of files to count in parallel",
+        value_name = "N",
+        default_value_t = 1,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    pub jobs: usize,
+
+    #[arg(
+        long = "format",
+        value_enum,
+        default_value = "text",
+        help = "Print the counts as aligned columns (text), one JSON object per line (json), or comma (csv) or tab (tsv) separated values"
+    )]
+    pub format: Format,
+
+    #[arg(
+        long = "word-mode",
+        value_enum,
+        value_name = "MODE",
+        default_value = "whitespace",
+        help = "Split words at whitespace, at Unicode word boundaries (unicode) or count the matches of --word-regex (regex)"
+    )]
+    pub word_mode: WordMode,
+
+    #[arg(
+        long = "word-regex",
+        value_name = "PATTERN",
+        help = "Pattern matching one word, used with --word-mode=regex",
+        required_if_eq("word_mode", "regex")
+    )]
+    pub word_regex: Option<Regex>,
+
+    #[arg(
+        long = "total",
+        value_enum,
+        value_name = "WHEN",
+        default_value = "auto",
+        help = "Print a total line when more than one file is given (auto), always, only the total (only) or never"
+    )]
+    pub total: Total,
+
+    #[arg(
+        short = 'R',
+        long = "recursive",
+        help = "Walk the given directories and print files, lines, blank and comment lines per language",
+        conflicts_with_all = ["files0_from", "format"]
+    )]
+    pub recursive: bool,
+
+    #[arg(
+        long = "include",
+        value_name = "GLOB",
+        help = "With -R, only count files whose name or path matches GLOB",
+        requires = "recursive"
+    )]
+    pub include: Vec<glob::Pattern>,
+
+    #[arg(
+        long = "exclude",
+        value_name = "GLOB",
+        help = "With -R, skip files and directories whose name or path matches GLOB",
+        requires = "recursive"
+    )]
+    pub exclude: Vec<glob::Pattern>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, clap::ValueEnum, Debug)]
+pub enum Total {
+    Auto,
+    Always,
+    Only,
+    Never,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, clap::ValueEnum, Debug)]
+pub enum WordMode {
+    Whitespace,
+    Unicode,
+    Regex,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, clap::ValueEnum, Debug)]
+pub enum Format {
+    Text,
+    Json,
+    Csv,
+    Tsv,
 }
 
 #[cfg(test)]
 mod tests {
-    use super::count;
+    use super::{count, count_with, Config, FileInfo};
+    use clap::Parser;
     use pretty_assertions::assert_eq;
+    use std::io::Read;
+
+    const INVALID: &[u8] = b"ab\xffcd \xe6\x97 x\xe6\x97\xa5\n";
+
+    struct OneByteReader<'a>(&'a [u8]);
+
+    impl Read for OneByteReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let Some((&first, rest)) = self.0.split_first() else {
+                return Ok(0);
+            };
+
+            buf[0] = first;
+            self.0 = rest;
+            Ok(1)
+        }
+    }
+
+    fn config() -> Config {
+        Config::try_parse_from(["wcr", "-lwmL"]).unwrap()
+    }
+
+    fn max_line_length(text: &str) -> usize {
+        count(text.as_bytes(), &config()).unwrap().max_line_length
+    }
 
     #[test]
     fn count_works() {
         let text = "The quick brown fox jumps over the lazy dog.";
         let mut cursor = std::io::Cursor::new(text);
-        let result = count(&mut cursor).unwrap();
-        let expected = (1, 9, 44, 44);
+        let result = count(&mut cursor, &config()).unwrap();
+        let expected = FileInfo {
+            num_lines: 0,
+            num_words: 9,
+            num_chars: 44,
+            num_bytes: 44,
+            max_line_length: 44,
+        };
+
+        assert_eq!(result, expected)
+    }
+
+    #[test]
+    fn max_line_length_works() {
+        assert_eq!(max_line_length(""), 0);
+        assert_eq!(max_line_length("abc\nab\n"), 3);
+        assert_eq!(max_line_length("a\tb"), 9);
+        assert_eq!(max_line_length("1234567\t\t"), 16);
+        assert_eq!(max_line_length("日本語"), 6);
+        assert_eq!(max_line_length("e\u{301}"), 1);
+        assert_eq!(max_line_length("long line\rab"), 9);
+    }
+
+    #[test]
+    fn count_tolerates_invalid_utf8() {
+        let result = count(INVALID, &config()).unwrap();
+        let expected = FileInfo {
+            num_lines: 1,
+            num_words: 2,
+            num_chars: 11,
+            num_bytes: 14,
+            max_line_length: 9,
+        };
 
         assert_eq!(result, expected)
     }
+
+    #[test]
+    fn count_handles_sequences_split_across_reads() {
+        let result = count(OneByteReader(INVALID), &config()).unwrap();
+        let expected = count(INVALID, &config()).unwrap();
+
+        assert_eq!(result, expected);
+
+        let result = count(OneByteReader(b"\xe6\x97"), &config()).unwrap();
+
+        assert_eq!(result.num_chars, 1)
+    }
+
+    #[test]
+    fn count_with_hands_out_counted_lines() {
+        let text = b"one\n\ntwo three\nlast";
+        let mut lines = vec![];
+        let on_line = |line: &[u8]| lines.push(line.to_vec());
+        let result = count_with(OneByteReader(text), &config(), Some(on_line)).unwrap();
+
+        assert_eq!(result.num_lines, 3);
+        assert_eq!(lines, [&b"one"[..], b"", b"two three"]);
+    }
 }
@@ -3,7 +3,7 @@ use clap::Parser;
 fn main() {
     let mut config = wcr::Config::parse();
 
-    if !config.lines && !config.words && !config.chars && !config.bytes {
+    if !config.lines && !config.words && !config.chars && !config.bytes && !config.max_line_length {
         config.lines = true;
         config.words = true;
         config.bytes = true;
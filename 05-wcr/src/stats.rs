@@ -0,0 +1,479 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::Path;
+
+use walkdir::{DirEntry, WalkDir};
+
+use crate::{count_with, Config, Result};
+
+struct Language {
+    name: &'static str,
+    extensions: &'static [&'static str],
+    file_names: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+}
+
+const C_BLOCK: Option<(&str, &str)> = Some(("/*", "*/"));
+
+const LANGUAGES: &[Language] = &[
+    Language {
+        name: "C",
+        extensions: &["c", "h"],
+        file_names: &[],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "C++",
+        extensions: &["cc", "cpp", "cxx", "hh", "hpp", "hxx"],
+        file_names: &[],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "C#",
+        extensions: &["cs"],
+        file_names: &[],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "CSS",
+        extensions: &["css"],
+        file_names: &[],
+        line_comments: &[],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "Go",
+        extensions: &["go"],
+        file_names: &[],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "HTML",
+        extensions: &["htm", "html"],
+        file_names: &[],
+        line_comments: &[],
+        block_comment: Some(("<!--", "-->")),
+    },
+    Language {
+        name: "Haskell",
+        extensions: &["hs"],
+        file_names: &[],
+        line_comments: &["--"],
+        block_comment: Some(("{-", "-}")),
+    },
+    Language {
+        name: "Java",
+        extensions: &["java"],
+        file_names: &[],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "JavaScript",
+        extensions: &["cjs", "js", "jsx", "mjs"],
+        file_names: &[],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "JSON",
+        extensions: &["json"],
+        file_names: &[],
+        line_comments: &[],
+        block_comment: None,
+    },
+    Language {
+        name: "Kotlin",
+        extensions: &["kt", "kts"],
+        file_names: &[],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "Lua",
+        extensions: &["lua"],
+        file_names: &[],
+        line_comments: &["--"],
+        block_comment: Some(("--[[", "]]")),
+    },
+    Language {
+        name: "Makefile",
+        extensions: &["mk"],
+        file_names: &["GNUmakefile", "Makefile", "makefile"],
+        line_comments: &["#"],
+        block_comment: None,
+    },
+    Language {
+        name: "Markdown",
+        extensions: &["md", "markdown"],
+        file_names: &[],
+        line_comments: &[],
+        block_comment: Some(("<!--", "-->")),
+    },
+    Language {
+        name: "Perl",
+        extensions: &["pl", "pm"],
+        file_names: &[],
+        line_comments: &["#"],
+        block_comment: None,
+    },
+    Language {
+        name: "Python",
+        extensions: &["py", "pyi"],
+        file_names: &[],
+        line_comments: &["#"],
+        block_comment: None,
+    },
+    Language {
+        name: "Ruby",
+        extensions: &["rb"],
+        file_names: &["Gemfile", "Rakefile"],
+        line_comments: &["#"],
+        block_comment: None,
+    },
+    Language {
+        name: "Rust",
+        extensions: &["rs"],
+        file_names: &[],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "SQL",
+        extensions: &["sql"],
+        file_names: &[],
+        line_comments: &["--"],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "Shell",
+        extensions: &["bash", "sh", "zsh"],
+        file_names: &[],
+        line_comments: &["#"],
+        block_comment: None,
+    },
+    Language {
+        name: "Swift",
+        extensions: &["swift"],
+        file_names: &[],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "TOML",
+        extensions: &["toml"],
+        file_names: &[],
+        line_comments: &["#"],
+        block_comment: None,
+    },
+    Language {
+        name: "TypeScript",
+        extensions: &["mts", "ts", "tsx"],
+        file_names: &[],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "XML",
+        extensions: &["svg", "xml"],
+        file_names: &[],
+        line_comments: &[],
+        block_comment: Some(("<!--", "-->")),
+    },
+    Language {
+        name: "YAML",
+        extensions: &["yaml", "yml"],
+        file_names: &[],
+        line_comments: &["#"],
+        block_comment: None,
+    },
+];
+
+// Files in a language we do not know are grouped by their extension, and get
+// no comment lines.
+const UNKNOWN: Language = Language {
+    name: "",
+    extensions: &[],
+    file_names: &[],
+    line_comments: &[],
+    block_comment: None,
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LanguageStats {
+    pub files: usize,
+    pub lines: usize,
+    pub blank: usize,
+    pub comment: usize,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum LineKind {
+    Blank,
+    Comment,
+    Code,
+}
+
+pub fn run(config: &Config) -> Result<()> {
+    let mut groups: BTreeMap<String, LanguageStats> = BTreeMap::new();
+    let mut failed = 0;
+
+    for root in &config.files {
+        let root = if root == "-" { "." } else { root.as_str() };
+
+        let entries = WalkDir::new(root)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|entry| entry.depth() == 0 || !is_excluded(entry, root, config));
+
+        for entry in entries {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(error) => {
+                    eprintln!("{error}");
+                    failed += 1;
+                    continue;
+                }
+            };
+
+            if !entry.file_type().is_file() || !is_included(&entry, root, config) {
+                continue;
+            }
+
+            let (group, language) = detect_language(entry.path());
+
+            match file_stats(entry.path(), language, config) {
+                Ok(stats) => {
+                    let total = groups.entry(group).or_default();
+                    total.files += stats.files;
+                    total.lines += stats.lines;
+                    total.blank += stats.blank;
+                    total.comment += stats.comment;
+                }
+                Err(error) => {
+                    eprintln!("{}: {error}", entry.path().display());
+                    failed += 1;
+                }
+            }
+        }
+    }
+
+    let mut total = LanguageStats::default();
+
+    for stats in groups.values() {
+        total.files += stats.files;
+        total.lines += stats.lines;
+        total.blank += stats.blank;
+        total.comment += stats.comment;
+    }
+
+    let mut rows = vec![(
+        "language".to_string(),
+        ["files", "lines", "blank", "comment"].map(String::from),
+    )];
+
+    for (group, stats) in groups.into_iter().chain([("total".to_string(), total)]) {
+        rows.push((
+            group,
+            [stats.files, stats.lines, stats.blank, stats.comment].map(|n| n.to_string()),
+        ));
+    }
+
+    let name_width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    let width = rows
+        .iter()
+        .flat_map(|(_, values)| values.iter().map(String::len))
+        .max()
+        .unwrap_or(0);
+
+    for (name, values) in rows {
+        let values: Vec<_> = values
+            .iter()
+            .map(|value| format!("{:>width$}", value))
+            .collect();
+
+        println!("{:name_width$}  {}", name, values.join("  "));
+    }
+
+    if failed > 0 {
+        return Err(format!("{failed} file(s) could not be counted").into());
+    }
+
+    Ok(())
+}
+
+fn matches(patterns: &[glob::Pattern], entry: &DirEntry, root: &str) -> bool {
+    let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+
+    patterns.iter().any(|pattern| {
+        pattern.matches_path(relative)
+            || entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| pattern.matches(name))
+    })
+}
+
+fn is_included(entry: &DirEntry, root: &str, config: &Config) -> bool {
+    config.include.is_empty() || matches(&config.include, entry, root)
+}
+
+fn is_excluded(entry: &DirEntry, root: &str, config: &Config) -> bool {
+    matches(&config.exclude, entry, root)
+}
+
+fn detect_language(path: &Path) -> (String, &'static Language) {
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("");
+    let extension = path.extension().and_then(|extension| extension.to_str());
+
+    let language = LANGUAGES.iter().find(|language| {
+        language.file_names.contains(&file_name)
+            || extension.is_some_and(|extension| {
+                language
+                    .extensions
+                    .contains(&extension.to_ascii_lowercase().as_str())
+            })
+    });
+
+    match (language, extension) {
+        (Some(language), _) => (language.name.to_string(), language),
+        (None, Some(extension)) => (format!(".{extension}"), &UNKNOWN),
+        (None, None) => ("(no extension)".to_string(), &UNKNOWN),
+    }
+}
+
+// Lines are counted by the same engine as `count`, so a last line without a
+// newline is not a line here either, just as in the plain `wcr -l` output.
+fn file_stats(path: &Path, language: &Language, config: &Config) -> Result<LanguageStats> {
+    let mut stats = LanguageStats {
+        files: 1,
+        ..LanguageStats::default()
+    };
+
+    let mut in_block = false;
+    let on_line = |line: &[u8]| {
+        let line = String::from_utf8_lossy(line);
+
+        match classify(&line, language, &mut in_block) {
+            LineKind::Blank => stats.blank += 1,
+            LineKind::Comment => stats.comment += 1,
+            LineKind::Code => {}
+        }
+    };
+
+    let info = count_with(File::open(path)?, config, Some(on_line))?;
+    stats.lines = info.num_lines;
+
+    Ok(stats)
+}
+
+// A line is a comment when it holds nothing but comments, and code as soon as
+// anything else appears on it. Comment markers inside string literals are not
+// recognized.
+fn classify(line: &str, language: &Language, in_block: &mut bool) -> LineKind {
+    let mut rest = line.trim();
+
+    if rest.is_empty() {
+        return LineKind::Blank;
+    }
+
+    loop {
+        if *in_block {
+            let (_, end) = language.block_comment.unwrap_or_default();
+
+            match rest.find(end) {
+                Some(index) => {
+                    *in_block = false;
+                    rest = rest[index + end.len()..].trim_start();
+                }
+                None => return LineKind::Comment,
+            }
+
+            if rest.is_empty() {
+                return LineKind::Comment;
+            }
+        }
+
+        // A block start can begin with a line comment prefix, like `--[[` in
+        // Lua, so it is looked for first.
+        if let Some((start, _)) = language.block_comment {
+            if rest.starts_with(start) {
+                *in_block = true;
+                rest = &rest[start.len()..];
+                continue;
+            }
+        }
+
+        if language
+            .line_comments
+            .iter()
+            .any(|prefix| rest.starts_with(prefix))
+        {
+            return LineKind::Comment;
+        }
+
+        match language.block_comment {
+            Some((start, end)) => {
+                if let Some(index) = rest.rfind(start) {
+                    *in_block = !rest[index + start.len()..].contains(end);
+                }
+
+                return LineKind::Code;
+            }
+            None => return LineKind::Code,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{classify, LineKind, LANGUAGES};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn classify_works() {
+        let rust = LANGUAGES.iter().find(|l| l.name == "Rust").unwrap();
+        let lines = [
+            ("fn main() {", LineKind::Code),
+            ("", LineKind::Blank),
+            ("    // a comment", LineKind::Comment),
+            ("    /* a block", LineKind::Comment),
+            ("", LineKind::Blank),
+            ("       comment */", LineKind::Comment),
+            ("    /* short */ let x = 1;", LineKind::Code),
+            ("    let y = 2; /* trailing", LineKind::Code),
+            ("    */", LineKind::Comment),
+            ("}", LineKind::Code),
+        ];
+
+        let mut in_block = false;
+
+        for (line, expected) in lines {
+            assert_eq!(classify(line, rust, &mut in_block), expected, "{line}");
+        }
+
+        let lua = LANGUAGES.iter().find(|l| l.name == "Lua").unwrap();
+        let lines = [
+            ("-- a comment", LineKind::Comment),
+            ("--[[ a block", LineKind::Comment),
+            ("local x = 1", LineKind::Comment),
+            ("]]", LineKind::Comment),
+            ("--[[ short ]] local y = 2", LineKind::Code),
+            ("print(x)", LineKind::Code),
+        ];
+
+        let mut in_block = false;
+
+        for (line, expected) in lines {
+            assert_eq!(classify(line, lua, &mut in_block), expected, "{line}");
+        }
+    }
+}
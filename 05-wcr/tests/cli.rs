@@ -9,6 +9,10 @@ const PRG: &str = "wcr";
 const EMPTY: &str = "tests/inputs/empty.txt";
 const FOX: &str = "tests/inputs/fox.txt";
 const ATLAMAL: &str = "tests/inputs/atlamal.txt";
+const WIDE: &str = "tests/inputs/wide.txt";
+const INVALID: &str = "tests/inputs/invalid.txt";
+const PROJECT: &str = "tests/inputs/project";
+const WORDS: &str = "tests/inputs/words.txt";
 
 // --------------------------------------------------
 fn gen_bad_file() -> String {
@@ -117,6 +121,12 @@ fn fox_bytes_lines() -> Result<()> {
     run(&["-l", "-c", FOX], "tests/expected/fox.txt.cl.out")
 }
 
+// --------------------------------------------------
+#[test]
+fn fox_max_line_length() -> Result<()> {
+    run(&["-L", FOX], "tests/expected/fox.txt.L.out")
+}
+
 // --------------------------------------------------
 #[test]
 fn atlamal() -> Result<()> {
@@ -159,6 +169,39 @@ fn atlamal_bytes_lines() -> Result<()> {
     run(&["-l", "-c", ATLAMAL], "tests/expected/atlamal.txt.cl.out")
 }
 
+// --------------------------------------------------
+#[test]
+fn atlamal_max_line_length() -> Result<()> {
+    run(
+        &["--max-line-length", ATLAMAL],
+        "tests/expected/atlamal.txt.L.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn empty_max_line_length() -> Result<()> {
+    run(&["-L", EMPTY], "tests/expected/empty.txt.L.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn wide_max_line_length() -> Result<()> {
+    run(&["-L", WIDE], "tests/expected/wide.txt.L.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn wide_lines_max_line_length() -> Result<()> {
+    run(&["-lL", WIDE], "tests/expected/wide.txt.lL.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn invalid_utf8() -> Result<()> {
+    run(&[INVALID], "tests/expected/invalid.txt.out")
+}
+
 // --------------------------------------------------
 #[test]
 fn atlamal_stdin() -> Result<()> {
@@ -218,3 +261,300 @@ fn test_all_words_lines() -> Result<()> {
 fn test_all_bytes_lines() -> Result<()> {
     run(&["-cl", EMPTY, FOX, ATLAMAL], "tests/expected/all.cl.out")
 }
+
+// --------------------------------------------------
+#[test]
+fn test_all_max_line_length() -> Result<()> {
+    run(
+        &["-L", EMPTY, FOX, ATLAMAL, WIDE],
+        "tests/expected/all.L.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_lines_words_chars_max_line_length() -> Result<()> {
+    run(
+        &["-lwmL", EMPTY, FOX, ATLAMAL, WIDE],
+        "tests/expected/all.lwmL.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_jobs() -> Result<()> {
+    run(&["-j", "3", EMPTY, FOX, ATLAMAL], "tests/expected/all.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn jobs_keeps_order_and_skips_bad_file() -> Result<()> {
+    let bad = gen_bad_file();
+    let expected = fs::read_to_string("tests/expected/all.out")?;
+    Command::cargo_bin(PRG)?
+        .args(["--jobs", "2", EMPTY, FOX, &bad, ATLAMAL])
+        .assert()
+        .success()
+        .stdout(expected)
+        .stderr(predicate::str::is_match(format!(
+            "^{bad}: .* [(]os error 2[)]\n$"
+        ))?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_zero_jobs() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["-j", "0", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "invalid value '0' for '--jobs <N>'",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_json() -> Result<()> {
+    run(
+        &["--format", "json", EMPTY, FOX, ATLAMAL],
+        "tests/expected/all.json.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_csv() -> Result<()> {
+    run(
+        &["--format", "csv", EMPTY, FOX, ATLAMAL],
+        "tests/expected/all.csv.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn fox_lines_chars_tsv() -> Result<()> {
+    run(
+        &["--format", "tsv", "-lm", FOX],
+        "tests/expected/fox.txt.lm.tsv.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn json_reports_bad_file() -> Result<()> {
+    let bad = gen_bad_file();
+    let expected = format!(
+        "^\\{{\"name\":\"{bad}\",\"error\":\".* [(]os error 2[)]\"\\}}\n"
+    );
+    Command::cargo_bin(PRG)?
+        .args(["--format", "json", &bad])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(expected)?)
+        .stderr(predicate::str::contains(bad));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_total_only() -> Result<()> {
+    run(
+        &["--total", "only", EMPTY, FOX, ATLAMAL],
+        "tests/expected/all.total-only.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_total_never() -> Result<()> {
+    run(
+        &["--total=never", EMPTY, FOX],
+        "tests/expected/all.total-never.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn fox_lines_total_always() -> Result<()> {
+    run(
+        &["-l", "--total", "always", FOX],
+        "tests/expected/fox.txt.l.total-always.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn files0_from() -> Result<()> {
+    let expected = fs::read_to_string("tests/expected/all.out")?;
+    Command::cargo_bin(PRG)?
+        .arg("--files0-from=tests/inputs/files0.txt")
+        .assert()
+        .failure()
+        .stdout(expected)
+        .stderr(predicate::str::starts_with(
+            "tests/inputs/files0.txt:3: invalid zero-length file name\n",
+        ))
+        .stderr(predicate::str::contains("1 file(s) could not be counted"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn files0_from_stdin() -> Result<()> {
+    let expected = fs::read_to_string("tests/expected/all.out")?;
+    Command::cargo_bin(PRG)?
+        .args(["--files0-from", "-"])
+        .write_stdin(format!("{EMPTY}\0{FOX}\0{ATLAMAL}"))
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_files0_from_and_files() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--files0-from", "tests/inputs/files0.txt", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "the argument '--files0-from <F>' cannot be used with '[FILES]...'",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn recursive() -> Result<()> {
+    run(&["-R", PROJECT], "tests/expected/project.R.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn recursive_include_exclude() -> Result<()> {
+    run(
+        &[
+            "--recursive",
+            "--exclude",
+            "vendor",
+            "--include",
+            "*.rs",
+            "--include",
+            "scripts/*",
+            PROJECT,
+        ],
+        "tests/expected/project.R.filtered.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn recursive_lines_match_plain_count() -> Result<()> {
+    let output = Command::cargo_bin(PRG)?
+        .args(["-l", "--total=only"])
+        .args([
+            "tests/inputs/project/data.xyz",
+            "tests/inputs/project/README.md",
+            "tests/inputs/project/scripts/build.sh",
+            "tests/inputs/project/src/lib.rs",
+            "tests/inputs/project/src/main.rs",
+            "tests/inputs/project/vendor/build.rs",
+        ])
+        .output()?;
+    let stdout = String::from_utf8(output.stdout)?;
+    let expected = fs::read_to_string("tests/expected/project.R.out")?;
+    let total = expected.lines().last().unwrap();
+
+    assert_eq!(total.split_whitespace().nth(2), Some(stdout.trim()));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_recursive_bad_dir() -> Result<()> {
+    let bad = gen_bad_file();
+    Command::cargo_bin(PRG)?
+        .args(["-R", &bad])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(bad))
+        .stderr(predicate::str::contains("1 file(s) could not be counted"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_include_without_recursive() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--include", "*.rs", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--recursive"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn words_whitespace() -> Result<()> {
+    run(&["-w", WORDS], "tests/expected/words.txt.w.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn words_unicode() -> Result<()> {
+    run(
+        &["-w", "--word-mode", "unicode", WORDS],
+        "tests/expected/words.txt.w.unicode.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn words_regex() -> Result<()> {
+    run(
+        &["-lw", "--word-mode=regex", "--word-regex", r"\p{L}+", WORDS],
+        "tests/expected/words.txt.lw.regex.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_word_mode_regex_without_pattern() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--word-mode", "regex", WORDS])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--word-regex <PATTERN>"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn bytes_of_file_with_zero_size() -> Result<()> {
+    let path = "/proc/version";
+    let expected = fs::read(path)?.len();
+
+    Command::cargo_bin(PRG)?
+        .args(["-c", path])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(format!("{expected} ")));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_read_error() -> Result<()> {
+    let expected = fs::read_to_string("tests/expected/fox.txt.out")?;
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", FOX])
+        .assert()
+        .failure()
+        .stdout(predicate::str::starts_with(expected))
+        .stderr(predicate::str::contains("tests/inputs: Is a directory"));
+    Ok(())
+}
@@ -0,0 +1,4 @@
+#!/bin/sh
+# Build everything.
+
+cargo build --release
@@ -0,0 +1,8 @@
+// Entry point.
+fn main() {
+    /* Print a greeting
+       to the terminal. */
+    println!("Hello, world!");
+
+    let answer = 42; // the answer
+}
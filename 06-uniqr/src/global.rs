@@ -0,0 +1,264 @@
+use std::cmp::Reverse;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BinaryHeap, HashMap};
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, BufReader, BufWriter, ErrorKind, Read, Seek, SeekFrom, Write};
+
+use anyhow::Result;
+
+use crate::{comparison_key, Config};
+
+// Once the distinct lines no longer fit in memory, every line is written to
+// one of these files by the hash of its key, so each file can be counted on
+// its own with a fraction of the memory.
+const PARTITIONS: u64 = 64;
+
+struct Entry {
+    index: u64,
+    count: u64,
+    line: String,
+}
+
+pub fn run(config: &Config, mut file: impl BufRead, output: &mut impl Write) -> Result<()> {
+    let mut table = HashMap::new();
+    let mut partitions: Option<Vec<BufWriter<File>>> = None;
+    let mut line = String::new();
+    let mut index = 0;
+
+    loop {
+        line.clear();
+
+        if file.read_line(&mut line)? == 0 {
+            break;
+        }
+
+        // Lines no longer keep their place in the input, so the last one
+        // needs a newline like every other.
+        if !line.ends_with('\n') {
+            line.push('\n');
+        }
+
+        let key = key(&line, config);
+        let entry = Entry {
+            index,
+            count: 1,
+            line: line.clone(),
+        };
+
+        match &mut partitions {
+            Some(partitions) => write_entry(&mut partitions[partition(&key)], &key, &entry)?,
+            None => {
+                add(&mut table, key, entry);
+
+                if table.len() > config.max_distinct {
+                    partitions = Some(spill(&mut table)?);
+                }
+            }
+        }
+
+        index += 1;
+    }
+
+    let top = config.top;
+
+    let Some(partitions) = partitions else {
+        let mut entries: Vec<Entry> = table.into_values().collect();
+
+        match top {
+            Some(top) => print_top(output, select_top(entries, top))?,
+            None => {
+                entries.sort_by_key(|entry| entry.index);
+
+                for entry in &entries {
+                    print_entry(output, config, entry)?;
+                }
+            }
+        }
+
+        return Ok(());
+    };
+
+    let mut candidates = vec![];
+    let mut results = vec![];
+
+    for partition in partitions {
+        let mut table = HashMap::new();
+        let mut reader = rewind(partition)?;
+
+        while let Some((key, entry)) = read_entry(&mut reader)? {
+            add(&mut table, key, entry);
+        }
+
+        let mut entries: Vec<Entry> = table.into_values().collect();
+
+        match top {
+            Some(top) => {
+                candidates.extend(entries);
+                candidates = select_top(candidates, top);
+            }
+            None => {
+                entries.sort_by_key(|entry| entry.index);
+
+                let mut result = BufWriter::new(tempfile::tempfile()?);
+
+                for entry in &entries {
+                    write_entry(&mut result, "", entry)?;
+                }
+
+                results.push(rewind(result)?);
+            }
+        }
+    }
+
+    if let Some(top) = top {
+        print_top(output, select_top(candidates, top))?;
+        return Ok(());
+    }
+
+    // Every result file is already in input order, so merging them by the
+    // index of their next line restores the order of the whole input.
+    let mut heap = BinaryHeap::new();
+    let mut heads = vec![];
+
+    for (position, reader) in results.iter_mut().enumerate() {
+        let head = read_entry(reader)?.map(|(_, entry)| entry);
+
+        if let Some(entry) = &head {
+            heap.push(Reverse((entry.index, position)));
+        }
+
+        heads.push(head);
+    }
+
+    while let Some(Reverse((_, position))) = heap.pop() {
+        if let Some(entry) = heads[position].take() {
+            print_entry(output, config, &entry)?;
+        }
+
+        if let Some((_, entry)) = read_entry(&mut results[position])? {
+            heap.push(Reverse((entry.index, position)));
+            heads[position] = Some(entry);
+        }
+    }
+
+    Ok(())
+}
+
+fn key(line: &str, config: &Config) -> String {
+    let key = comparison_key(line, config);
+
+    if config.ignore_case {
+        key.to_lowercase()
+    } else {
+        key.to_string()
+    }
+}
+
+fn add(table: &mut HashMap<String, Entry>, key: String, entry: Entry) {
+    match table.get_mut(&key) {
+        Some(existing) => {
+            existing.count += entry.count;
+
+            if entry.index < existing.index {
+                existing.index = entry.index;
+                existing.line = entry.line;
+            }
+        }
+        None => {
+            table.insert(key, entry);
+        }
+    }
+}
+
+fn partition(key: &str) -> usize {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    (hasher.finish() % PARTITIONS) as usize
+}
+
+fn spill(table: &mut HashMap<String, Entry>) -> io::Result<Vec<BufWriter<File>>> {
+    let mut partitions = (0..PARTITIONS)
+        .map(|_| tempfile::tempfile().map(BufWriter::new))
+        .collect::<io::Result<Vec<_>>>()?;
+
+    for (key, entry) in table.drain() {
+        write_entry(&mut partitions[partition(&key)], &key, &entry)?;
+    }
+
+    table.shrink_to_fit();
+
+    Ok(partitions)
+}
+
+fn rewind(writer: BufWriter<File>) -> io::Result<BufReader<File>> {
+    let mut file = writer.into_inner().map_err(|e| e.into_error())?;
+    file.seek(SeekFrom::Start(0))?;
+    Ok(BufReader::new(file))
+}
+
+fn write_entry(writer: &mut impl Write, key: &str, entry: &Entry) -> io::Result<()> {
+    writer.write_all(&entry.index.to_le_bytes())?;
+    writer.write_all(&entry.count.to_le_bytes())?;
+
+    for text in [key, entry.line.as_str()] {
+        writer.write_all(&(text.len() as u64).to_le_bytes())?;
+        writer.write_all(text.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+fn read_entry(reader: &mut impl Read) -> io::Result<Option<(String, Entry)>> {
+    let index = match read_u64(reader) {
+        Ok(index) => index,
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    let count = read_u64(reader)?;
+    let key = read_string(reader)?;
+    let line = read_string(reader)?;
+
+    Ok(Some((key, Entry { index, count, line })))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_string(reader: &mut impl Read) -> io::Result<String> {
+    let mut bytes = vec![0; read_u64(reader)? as usize];
+    reader.read_exact(&mut bytes)?;
+    String::from_utf8(bytes).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+}
+
+// The most frequent lines first, and the one seen first among equally
+// frequent lines.
+fn select_top(mut entries: Vec<Entry>, top: usize) -> Vec<Entry> {
+    entries.sort_by_key(|entry| (Reverse(entry.count), entry.index));
+    entries.truncate(top);
+    entries
+}
+
+fn print_top(output: &mut impl Write, entries: Vec<Entry>) -> io::Result<()> {
+    for entry in entries {
+        write!(output, "{:4} {}", entry.count, entry.line)?;
+    }
+
+    Ok(())
+}
+
+fn print_entry(output: &mut impl Write, config: &Config, entry: &Entry) -> io::Result<()> {
+    if (config.repeated && entry.count == 1) || (config.unique && entry.count > 1) {
+        return Ok(());
+    }
+
+    if config.count {
+        write!(output, "{:4} {}", entry.count, entry.line)
+    } else {
+        output.write_all(entry.line.as_bytes())
+    }
+}
@@ -1,4 +1,10 @@
-use std::io::{BufRead, Write};
+mod global;
+
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::Path;
+
+use tempfile::NamedTempFile;
 
 use anyhow::{Ok, Result};
 use clap::*;
@@ -14,56 +20,131 @@ pub fn run(config: Config) -> Result<()> {
         std::process::exit(1);
     });
 
-    let mut current_line = String::new();
-    let mut next_line = String::new();
-
-    let bytes_read = file.read_line(&mut current_line)?;
-
-    if bytes_read == 0 {
-        return Ok(());
+    if config.global || config.top.is_some() {
+        global::run(&config, file, &mut output)?;
+        return output.finish();
     }
 
-    let mut counter = 1;
+    let mut line = String::new();
+    let mut group: Vec<String> = vec![];
+    let mut counter = 0;
+    let mut groups_printed = 0;
 
     loop {
-        let bytes_read = file.read_line(&mut next_line)?;
-        let mut count_string = String::new();
+        let bytes_read = file.read_line(&mut line)?;
+
+        if bytes_read == 0 || (counter > 0 && !same_key(&group[0], &line, &config)) {
+            if counter > 0 {
+                print_group(&mut output, &config, &group, counter, &mut groups_printed)?;
+            }
+
+            if bytes_read == 0 {
+                break;
+            }
 
-        if config.count {
-            count_string = format!("{:4} ", counter);
+            group.clear();
+            counter = 0;
         }
 
-        if bytes_read == 0 {
-            match config.output_file.clone() {
-                Some(_) => {
-                    let _ = output.write(format!("{}{}", count_string, current_line).as_bytes());
-                }
-                None => {
-                    print!("{}{}", count_string, current_line);
-                }
-            };
-            break;
+        // Only -D prints more than the first line of a group.
+        if counter == 0 || config.all_repeated.is_some() {
+            group.push(std::mem::take(&mut line));
+        } else {
+            line.clear();
         }
 
-        if current_line.trim() != next_line.trim() {
-            match config.output_file.clone() {
-                Some(_) => {
-                    let _ = output.write(format!("{}{}", count_string, current_line).as_bytes());
-                }
-                None => {
-                    print!("{}{}", count_string, current_line);
-                }
+        counter += 1;
+    }
+
+    output.finish()
+}
+
+// Lines are compared without their newline, after skipping the first
+// `skip_fields` fields (blanks followed by non-blanks) and then `skip_chars`
+// characters, looking at no more than `check_chars` characters.
+fn comparison_key<'a>(line: &'a str, config: &Config) -> &'a str {
+    let mut key = line.strip_suffix('\n').unwrap_or(line);
+
+    for _ in 0..config.skip_fields {
+        key = key.trim_start_matches([' ', '\t']);
+        key = key.trim_start_matches(|c| c != ' ' && c != '\t');
+    }
+
+    key = key
+        .char_indices()
+        .nth(config.skip_chars)
+        .map_or("", |(index, _)| &key[index..]);
+
+    if let Some(check_chars) = config.check_chars {
+        key = key
+            .char_indices()
+            .nth(check_chars)
+            .map_or(key, |(index, _)| &key[..index]);
+    }
+
+    key
+}
+
+fn same_key(line: &str, other: &str, config: &Config) -> bool {
+    let key = comparison_key(line, config);
+    let other = comparison_key(other, config);
+
+    if config.ignore_case {
+        key.chars()
+            .flat_map(char::to_lowercase)
+            .eq(other.chars().flat_map(char::to_lowercase))
+    } else {
+        key == other
+    }
+}
+
+fn print_group(
+    output: &mut impl Write,
+    config: &Config,
+    group: &[String],
+    counter: usize,
+    groups_printed: &mut usize,
+) -> Result<()> {
+    match config.all_repeated {
+        Some(delimit) => {
+            if counter == 1 {
+                return Ok(());
+            }
+
+            let separator = match delimit {
+                Delimit::None => false,
+                Delimit::Prepend => true,
+                Delimit::Separate => *groups_printed > 0,
             };
 
-            current_line = next_line.clone();
-            counter = 1;
-        } else {
-            counter += 1;
+            if separator {
+                output.write_all(b"\n")?;
+            }
+
+            // Like GNU uniq, -D with -u drops the first line of every group.
+            let skip = if config.unique { 1 } else { 0 };
+
+            for line in &group[skip..] {
+                output.write_all(line.as_bytes())?;
+            }
         }
+        None => {
+            if (config.repeated && counter == 1) || (config.unique && counter > 1) {
+                return Ok(());
+            }
+
+            let mut count_string = String::new();
 
-        next_line.clear();
+            if config.count {
+                count_string = format!("{:4} ", counter);
+            }
+
+            write!(output, "{}{}", count_string, group[0])?;
+        }
     }
 
+    *groups_printed += 1;
+
     Ok(())
 }
 
@@ -76,13 +157,98 @@ fn open_file(file_path: &str) -> Result<Box<dyn BufRead>> {
     }
 }
 
-fn create_output(file_path: Option<String>) -> Result<Box<dyn std::io::Write>> {
-    match file_path {
-        Some(file_path) => Ok(Box::new(std::fs::File::create(file_path.as_str())?)),
-        None => Ok(Box::new(std::io::stdout())),
+// A regular output file is written next to its final location and only
+// renamed over it once everything has been written, so a failed run leaves
+// any previous file untouched. Anything else, like a device or a FIFO, is
+// written to directly.
+enum Output {
+    Stdout(BufWriter<io::Stdout>),
+    File(BufWriter<NamedTempFile>, String),
+    Direct(BufWriter<File>),
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::Stdout(writer) => writer.write(buf),
+            Output::File(writer, _) => writer.write(buf),
+            Output::Direct(writer) => writer.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Stdout(writer) => writer.flush(),
+            Output::File(writer, _) => writer.flush(),
+            Output::Direct(writer) => writer.flush(),
+        }
     }
 }
 
+impl Output {
+    fn finish(self) -> Result<()> {
+        match self {
+            Output::Stdout(mut writer) => writer.flush()?,
+            Output::Direct(mut writer) => writer.flush()?,
+            Output::File(writer, file_path) => {
+                let file = writer.into_inner().map_err(|e| e.into_error())?;
+                file.as_file().sync_all()?;
+                file.persist(&file_path)
+                    .map_err(|e| anyhow::anyhow!("{file_path}: {}", e.error))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn create_output(file_path: Option<String>) -> Result<Output> {
+    let Some(file_path) = file_path else {
+        return Ok(Output::Stdout(BufWriter::new(io::stdout())));
+    };
+
+    let metadata = fs::symlink_metadata(&file_path).ok();
+
+    if metadata
+        .as_ref()
+        .is_some_and(|metadata| !metadata.file_type().is_file())
+    {
+        let file = File::create(&file_path).map_err(|e| anyhow::anyhow!("{file_path}: {e}"))?;
+        return Ok(Output::Direct(BufWriter::new(file)));
+    }
+
+    let directory = match Path::new(&file_path).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    let file = temp_file_builder()
+        .tempfile_in(directory)
+        .map_err(|e| anyhow::anyhow!("{file_path}: {e}"))?;
+
+    if let Some(metadata) = metadata {
+        file.as_file().set_permissions(metadata.permissions())?;
+    }
+
+    Ok(Output::File(BufWriter::new(file), file_path))
+}
+
+// On Unix a new file gets the same mode the umask would give it when created
+// directly, rather than the private mode of a temporary file.
+#[cfg(unix)]
+fn temp_file_builder<'a>() -> tempfile::Builder<'a, 'a> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut builder = tempfile::Builder::new();
+    builder.permissions(fs::Permissions::from_mode(0o666));
+    builder
+}
+
+#[cfg(not(unix))]
+fn temp_file_builder<'a>() -> tempfile::Builder<'a, 'a> {
+    tempfile::Builder::new()
+}
+
 #[derive(Debug, Parser)]
 #[command(version, about)]
 pub struct Config {
@@ -98,4 +264,95 @@ pub struct Config {
         help = "Show the number of occurences before each line"
     )]
     count: bool,
+
+    #[arg(
+        short = 'd',
+        long = "repeated",
+        help = "Only print one line of each group of duplicate lines"
+    )]
+    repeated: bool,
+
+    #[arg(
+        short = 'u',
+        long = "unique",
+        help = "Only print lines that are not repeated"
+    )]
+    unique: bool,
+
+    #[arg(
+        short = 'D',
+        long = "all-repeated",
+        value_enum,
+        value_name = "METHOD",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "none",
+        conflicts_with = "count",
+        help = "Print every line of each group of duplicate lines, with no separator (none), an empty line before each group (prepend) or between groups (separate)"
+    )]
+    all_repeated: Option<Delimit>,
+
+    #[arg(
+        short = 'f',
+        long = "skip-fields",
+        value_name = "N",
+        default_value_t = 0,
+        help = "Avoid comparing the first N fields"
+    )]
+    skip_fields: usize,
+
+    #[arg(
+        short = 's',
+        long = "skip-chars",
+        value_name = "N",
+        default_value_t = 0,
+        help = "Avoid comparing the first N characters"
+    )]
+    skip_chars: usize,
+
+    #[arg(
+        short = 'w',
+        long = "check-chars",
+        value_name = "N",
+        help = "Compare no more than N characters in lines"
+    )]
+    check_chars: Option<usize>,
+
+    #[arg(
+        short = 'i',
+        long = "ignore-case",
+        help = "Ignore differences in case when comparing"
+    )]
+    ignore_case: bool,
+
+    #[arg(
+        long = "global",
+        conflicts_with = "all_repeated",
+        help = "Remove duplicate lines anywhere in the input, keeping the first occurrence of each"
+    )]
+    global: bool,
+
+    #[arg(
+        long = "top",
+        value_name = "N",
+        conflicts_with_all = ["global", "all_repeated", "repeated", "unique"],
+        help = "Print the N most frequent lines with their counts"
+    )]
+    top: Option<usize>,
+
+    #[arg(
+        long = "max-distinct",
+        value_name = "N",
+        default_value_t = 1_000_000,
+        value_parser = builder::RangedU64ValueParser::<usize>::new().range(1..),
+        help = "Keep at most N distinct lines in memory with --global or --top before spilling to temporary files"
+    )]
+    max_distinct: usize,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+pub enum Delimit {
+    None,
+    Prepend,
+    Separate,
 }
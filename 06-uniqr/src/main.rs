@@ -4,7 +4,15 @@ fn main() {
     let config = uniqr::Config::parse();
 
     if let Err(e) = uniqr::run(config) {
-        eprint!("Ops, something went wrong while running the application: {e}");
+        // A reader that goes away early, like `head`, is not worth a message.
+        let broken_pipe = e
+            .downcast_ref::<std::io::Error>()
+            .is_some_and(|e| e.kind() == std::io::ErrorKind::BrokenPipe);
+
+        if !broken_pipe {
+            eprintln!("Ops, something went wrong while running the application: {e}");
+        }
+
         std::process::exit(1);
     }
 }
@@ -13,6 +13,8 @@ struct Test {
 }
 
 const PRG: &str = "uniqr";
+const LOG: &str = "tests/inputs/log.txt";
+const SPACES: &str = "tests/inputs/spaces.txt";
 
 const EMPTY: Test = Test {
     input: "tests/inputs/empty.txt",
@@ -617,3 +619,289 @@ fn t6_outfile_count() -> Result<()> {
 fn t6_stdin_outfile_count() -> Result<()> {
     run_stdin_outfile_count(&T6)
 }
+
+// --------------------------------------------------
+fn run_args(args: &[&str], expected_file: &str) -> Result<()> {
+    let expected = fs::read_to_string(expected_file)?;
+    let output = Command::cargo_bin(PRG)?.args(args).output().expect("fail");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("invalid UTF-8");
+    assert_eq!(stdout, expected);
+    Ok(())
+}
+
+#[test]
+fn three_repeated() -> Result<()> {
+    run_args(&["-d", THREE.input], "tests/expected/three.txt.d.out")
+}
+
+#[test]
+fn three_repeated_count() -> Result<()> {
+    run_args(
+        &["-d", "-c", THREE.input],
+        "tests/expected/three.txt.dc.out",
+    )
+}
+
+#[test]
+fn three_unique() -> Result<()> {
+    run_args(&["--unique", THREE.input], "tests/expected/three.txt.u.out")
+}
+
+#[test]
+fn three_unique_count() -> Result<()> {
+    run_args(&["-uc", THREE.input], "tests/expected/three.txt.uc.out")
+}
+
+#[test]
+fn three_repeated_unique() -> Result<()> {
+    run_args(&["-d", "-u", THREE.input], EMPTY.out)
+}
+
+#[test]
+fn three_all_repeated() -> Result<()> {
+    run_args(&["-D", THREE.input], "tests/expected/three.txt.D.out")
+}
+
+#[test]
+fn three_all_repeated_prepend() -> Result<()> {
+    run_args(
+        &["--all-repeated=prepend", THREE.input],
+        "tests/expected/three.txt.D-prepend.out",
+    )
+}
+
+#[test]
+fn three_all_repeated_separate() -> Result<()> {
+    run_args(
+        &["-D=separate", THREE.input],
+        "tests/expected/three.txt.D-separate.out",
+    )
+}
+
+#[test]
+fn three_all_repeated_unique() -> Result<()> {
+    run_args(
+        &["-D", "-u", THREE.input],
+        "tests/expected/three.txt.Du.out",
+    )
+}
+
+#[test]
+fn dies_all_repeated_count() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["-D", "-c", THREE.input])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with '--count'"));
+    Ok(())
+}
+
+#[test]
+fn spaces_compared_exactly() -> Result<()> {
+    run_args(&[SPACES], "tests/expected/spaces.txt.out")
+}
+
+#[test]
+fn log_skip_fields() -> Result<()> {
+    run_args(&["-f", "1", LOG], "tests/expected/log.txt.f1.out")
+}
+
+#[test]
+fn log_skip_fields_ignore_case() -> Result<()> {
+    run_args(
+        &["--skip-fields=1", "--ignore-case", LOG],
+        "tests/expected/log.txt.f1i.out",
+    )
+}
+
+#[test]
+fn log_skip_fields_ignore_case_count() -> Result<()> {
+    run_args(&["-c", "-f1", "-i", LOG], "tests/expected/log.txt.cf1i.out")
+}
+
+#[test]
+fn log_skip_chars_check_chars() -> Result<()> {
+    run_args(
+        &["-s", "20", "-w", "4", LOG],
+        "tests/expected/log.txt.s20w4.out",
+    )
+}
+
+#[test]
+fn log_check_chars() -> Result<()> {
+    run_args(
+        &["--check-chars", "10", LOG],
+        "tests/expected/log.txt.w10.out",
+    )
+}
+
+#[test]
+fn three_global() -> Result<()> {
+    run_args(
+        &["--global", THREE.input],
+        "tests/expected/three.txt.global.out",
+    )
+}
+
+#[test]
+fn three_global_count() -> Result<()> {
+    run_args(
+        &["--global", "-c", THREE.input],
+        "tests/expected/three.txt.global.c.out",
+    )
+}
+
+#[test]
+fn three_global_count_spilled() -> Result<()> {
+    run_args(
+        &["--global", "-c", "--max-distinct", "1", THREE.input],
+        "tests/expected/three.txt.global.c.out",
+    )
+}
+
+#[test]
+fn log_global_unique() -> Result<()> {
+    run_args(
+        &["--global", "-u", "-f1", "-i", LOG],
+        "tests/expected/log.txt.global.f1iu.out",
+    )
+}
+
+#[test]
+fn three_top() -> Result<()> {
+    run_args(
+        &["--top", "3", THREE.input],
+        "tests/expected/three.txt.top3.out",
+    )
+}
+
+#[test]
+fn three_top_spilled() -> Result<()> {
+    run_args(
+        &["--top", "3", "--max-distinct", "2", THREE.input],
+        "tests/expected/three.txt.top3.out",
+    )
+}
+
+#[test]
+fn log_global_skip_fields_ignore_case() -> Result<()> {
+    run_args(
+        &["--global", "-f", "1", "-i", LOG],
+        "tests/expected/log.txt.f1i.out",
+    )
+}
+
+#[test]
+fn dies_write_error() -> Result<()> {
+    let output = std::process::Command::new(assert_cmd::cargo::cargo_bin(PRG))
+        .arg(THREE.input)
+        .stdout(fs::File::create("/dev/full")?)
+        .output()?;
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(stderr.contains("No space left on device"));
+    Ok(())
+}
+
+#[test]
+fn broken_pipe_is_quiet() -> Result<()> {
+    let script = format!(
+        "seq 1 300000 | {} | head -n 1",
+        assert_cmd::cargo::cargo_bin(PRG).display()
+    );
+
+    Command::new("sh")
+        .args(["-c", &script])
+        .assert()
+        .success()
+        .stdout("1\n")
+        .stderr("");
+    Ok(())
+}
+
+#[test]
+fn failed_run_keeps_outfile() -> Result<()> {
+    let outfile = NamedTempFile::new()?;
+    let outpath = &outfile.path().to_str().unwrap();
+    fs::write(outpath, "previous contents\n")?;
+
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", outpath])
+        .assert()
+        .failure();
+
+    assert_eq!(fs::read_to_string(outpath)?, "previous contents\n");
+    Ok(())
+}
+
+#[test]
+fn outfile_can_replace_input() -> Result<()> {
+    let file = NamedTempFile::new()?;
+    let path = &file.path().to_str().unwrap();
+    fs::copy(THREE.input, path)?;
+
+    Command::cargo_bin(PRG)?
+        .args([path, path])
+        .assert()
+        .success()
+        .stdout("");
+
+    assert_eq!(fs::read_to_string(path)?, fs::read_to_string(THREE.out)?);
+    Ok(())
+}
+
+#[test]
+fn outfile_device_is_written_directly() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args([THREE.input, "/dev/null"])
+        .assert()
+        .success()
+        .stdout("");
+
+    assert!(!fs::symlink_metadata("/dev/null")?.file_type().is_file());
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn outfile_symlink_is_kept() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let target = dir.path().join("target.txt");
+    let link = dir.path().join("link.txt");
+    fs::write(&target, "previous contents\n")?;
+    std::os::unix::fs::symlink(&target, &link)?;
+
+    Command::cargo_bin(PRG)?
+        .args([THREE.input, link.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout("");
+
+    assert!(fs::symlink_metadata(&link)?.file_type().is_symlink());
+    assert_eq!(fs::read_to_string(&target)?, fs::read_to_string(THREE.out)?);
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn new_outfile_follows_umask() -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir()?;
+    let outpath = dir.path().join("out.txt");
+    let script = format!(
+        "umask 077 && {} {} {}",
+        assert_cmd::cargo::cargo_bin(PRG).display(),
+        THREE.input,
+        outpath.display()
+    );
+
+    Command::new("sh").args(["-c", &script]).assert().success();
+
+    let mode = fs::metadata(&outpath)?.permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    Ok(())
+}